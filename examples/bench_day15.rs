@@ -0,0 +1,51 @@
+// Compares day 15 part 1 on the raw input bytes (part1_bytes) with the &str
+// path, which first has to validate the input as UTF-8.
+//
+// Usage: `cargo run --release --example bench_day15`. Uses inputs/day15.txt if
+// it exists, otherwise a generated input of similar steps.
+
+use std::fs;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use advent_of_code_2023::day15;
+use advent_of_code_2023::solution_import::input_path;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+const RUNS: u32 = 20;
+
+/// Generates steps like "rn=1" or "cm-", about 20 MB in total
+fn generated_input() -> Vec<u8> {
+    let mut rng = StdRng::seed_from_u64(2023);
+    let steps = (0..4_000_000).map(|_| {
+        let label = (0..rng.gen_range(2..=6)).map(|_| rng.gen_range(b'a'..=b'z') as char).collect::<String>();
+        if rng.gen_bool(0.5) {
+            format!("{}={}", label, rng.gen_range(1..=9))
+        } else {
+            format!("{}-", label)
+        }
+    }).collect::<Vec<_>>();
+    steps.join(",").into_bytes()
+}
+
+/// Runs the function RUNS times and returns the answer and the average time
+fn time(f: impl Fn() -> i64) -> (i64, Duration) {
+    let now = Instant::now();
+    let answer = (0..RUNS).map(|_| black_box(f())).last().unwrap();
+    (answer, now.elapsed() / RUNS)
+}
+
+fn main() {
+    let (name, input) = match fs::read(input_path(15)) {
+        Ok(input) => (input_path(15), input),
+        Err(_) => ("generated input".to_string(), generated_input()),
+    };
+    println!("Day 15 part 1 on {} ({} bytes), average of {} runs", name, input.len(), RUNS);
+
+    let (str_answer, str_time) = time(|| day15::part1(std::str::from_utf8(&input).unwrap()));
+    let (bytes_answer, bytes_time) = time(|| day15::part1_bytes(&input));
+    assert_eq!(str_answer, bytes_answer);
+
+    println!("part1 (&str, with UTF-8 validation): {:.2?}", str_time);
+    println!("part1_bytes (&[u8]):                 {:.2?}", bytes_time);
+}
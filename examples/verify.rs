@@ -0,0 +1,40 @@
+// Verifies the solutions against the example inputs from the puzzle
+// descriptions, without needing any input files.
+//
+// Usage: `cargo run --example verify -- 13` for a single day,
+// or `cargo run --example verify` for all days.
+
+use std::env;
+use std::process::ExitCode;
+
+use advent_of_code_2023::dedent;
+use advent_of_code_2023::examples::EXAMPLES;
+use advent_of_code_2023::solution_import::*;
+use colored::Colorize;
+
+fn main() -> ExitCode {
+    let selected_day = env::args().nth(1).map(|s| s.parse::<u32>().expect("Invalid day"));
+    let solutions = solutions();
+
+    let mut failed = 0;
+    for example in EXAMPLES.iter().filter(|e| selected_day.map_or(true, |day| e.day == day)) {
+        let (_, part1, part2, _) = solutions.iter().find(|(day, ..)| *day == example.day).unwrap();
+        // The examples are indented to fit into the code
+        let input = dedent(example.input);
+
+        for (part, solution, expected) in [(1, part1, example.part1), (2, part2, example.part2)] {
+            let Some(expected) = expected else {
+                continue;
+            };
+            let answer = solution(&input);
+            if answer == expected {
+                println!("Day {} part {}: {} {}", example.day, part, answer, "ok".green());
+            } else {
+                println!("Day {} part {}: {} {} (expected {})", example.day, part, answer, "FAILED".red().bold(), expected);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
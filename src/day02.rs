@@ -9,28 +9,65 @@
 
 use crate::{utils::*, parse::alphanums};
 
-/// [red, green, blue]
-type Hand = [I; 3];
+/// Number of cubes per color
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+struct Cubes {
+    pub red: I,
+    pub green: I,
+    pub blue: I,
+}
+
+impl Cubes {
+    /// Maximum count of each color
+    pub fn max(self, other: Cubes) -> Cubes {
+        Cubes {
+            red: self.red.max(other.red),
+            green: self.green.max(other.green),
+            blue: self.blue.max(other.blue),
+        }
+    }
+
+    /// Product of the counts of all colors
+    pub fn power(&self) -> I {
+        self.red * self.green * self.blue
+    }
+}
+
+/// The cubes in the bag for part 1
+const DEFAULT_LIMIT: Cubes = Cubes { red: 12, green: 13, blue: 14 };
+
+/// Checks if the hand can be drawn from a bag that contains the limit cubes
+fn is_possible(hand: &Cubes, limit: &Cubes) -> bool {
+    hand.red <= limit.red && hand.green <= limit.green && hand.blue <= limit.blue
+}
 
 /// (game id, hands)
-type Game = (I, Vec<Hand>);
+type Game = (I, Vec<Cubes>);
 
-fn parse_hand(input: &str) -> Hand {
+/// Parses a hand, returning an error for unknown colors or invalid counts
+fn parse_hand(input: &str) -> Result<Cubes, String> {
     // Input looks link "3 red, 5 green, 4 blue".
     // Split by comma, then split by space.
-    let items = input
-        .split(",")
-        .map(|item| alphanums(item).pair())
-        .map(|(count, color)| (color, count.parse::<I>().unwrap()))
-        .vec();
-
-    // If a color is not found in the input, it gets count zero
-    let color_count = |color| items.iter().find(|(name, _)| name == color).map(|(_, n)| *n).unwrap_or(0);
+    // If a color is not found in the input, it keeps count zero
+    let mut hand = Cubes::default();
+    for item in input.split(",") {
+        let (count, color) = alphanums(item)
+            .into_iter()
+            .collect_tuple()
+            .ok_or_else(|| format!("Invalid cube count: {}", item.trim()))?;
+        let count = count.parse::<I>().map_err(|_| format!("Invalid cube count: {}", item.trim()))?;
+        match color.as_str() {
+            "red" => hand.red = count,
+            "green" => hand.green = count,
+            "blue" => hand.blue = count,
+            _ => return Err(format!("Unknown color: {}", color)),
+        }
+    }
 
-    ["red", "green", "blue"].map(color_count)
+    Ok(hand)
 }
 
-fn parse(input: &str) -> Vec<Game> {
+fn parse(input: &str) -> Result<Vec<Game>, String> {
     // Input looks like "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green"
     // Split by colon to separate the game id from the hands.
     // Then split by semicolons and parse each segment to get the hands.
@@ -42,27 +79,27 @@ fn parse(input: &str) -> Vec<Game> {
             let hands = s_hands
                 .split(";")
                 .map(parse_hand)
-                .vec();
-            (id, hands)
+                .try_vec()?;
+            Ok((id, hands))
         })
-        .vec()
+        .collect()
 }
 
-fn get_min_counts(games: Vec<Game>) -> impl Iterator<Item = (I, Hand)> {
+fn get_min_counts(games: Vec<Game>) -> impl Iterator<Item = (I, Cubes)> {
     // For each game, get the maximum count of each color seen in any hand of that game.
     games.into_iter().map(|(id, hands)| {
         (
             id,
-            hands.into_iter().reduce(|[ar, ag, ab], [br, bg, bb]| [ar.max(br), ag.max(bg), ab.max(bb)]).unwrap()
+            hands.into_iter().reduce(Cubes::max).unwrap()
         )
     })
 }
 
 pub fn part1(input: &str) -> I {
     // For each game, get the minimum count of cubes that must have been in the bag.
-    // Then sum the game ids for all games where the maximum count is below the threshold (12, 13, 14).
-    get_min_counts(parse(input))
-        .filter(|&(_, [r, g, b])| r <= 12 && g <= 13 && b <= 14)
+    // Then sum the game ids for all games that are possible with the default limit (12, 13, 14).
+    get_min_counts(parse(input).expect("Invalid input"))
+        .filter(|(_, hand)| is_possible(hand, &DEFAULT_LIMIT))
         .map(|(id, _)| id)
         .sum()
 }
@@ -70,8 +107,8 @@ pub fn part1(input: &str) -> I {
 pub fn part2(input: &str) -> I {
     // For each game, get the minimum count of cubes that must have been in the bag.
     // Then sum the product of the minimum counts for all games.
-    get_min_counts(parse(input))
-        .map(|(_, hand)| hand.into_iter().product::<I>())
+    get_min_counts(parse(input).expect("Invalid input"))
+        .map(|(_, hand)| hand.power())
         .sum()
 }
 
@@ -92,4 +129,30 @@ mod tests {
         assert_eq!(part2(input), 2286);
     }
 
+    #[test]
+    fn test_parse_hand() {
+        assert_eq!(parse_hand(" 3 blue, 4 red"), Ok(Cubes { red: 4, green: 0, blue: 3 }));
+        assert_eq!(parse_hand(" 1 red, 2 green, 6 blue"), Ok(Cubes { red: 1, green: 2, blue: 6 }));
+        assert!(parse_hand(" 3 ret, 2 green").unwrap_err().contains("ret"));
+        assert!(parse("Game 1: 3 blue; 3 ret").is_err());
+    }
+
+    #[test]
+    fn test_cubes() {
+        let a = Cubes { red: 4, green: 0, blue: 3 };
+        let b = Cubes { red: 1, green: 2, blue: 6 };
+        assert_eq!(a.max(b), Cubes { red: 4, green: 2, blue: 6 });
+        assert_eq!(a.max(b).power(), 48);
+        assert_eq!(a.power(), 0);
+    }
+
+    #[test]
+    fn test_is_possible() {
+        let hand = Cubes { red: 4, green: 2, blue: 6 };
+        assert!(is_possible(&hand, &DEFAULT_LIMIT));
+        assert!(is_possible(&hand, &Cubes { red: 4, green: 2, blue: 6 }));
+        assert!(!is_possible(&hand, &Cubes { red: 5, green: 5, blue: 5 }));
+        assert!(!is_possible(&hand, &Cubes { red: 3, green: 10, blue: 10 }));
+    }
+
 }
\ No newline at end of file
@@ -7,8 +7,12 @@
 ///    - Part 2: Find all stars with exactly two adjacent numbers,
 ///      multiply the two numbers and sum the results
 
+use std::collections::HashMap;
+use itertools::Itertools;
+use crate::utils::{AsInt, Usize};
+
 type Row = Vec<char>;
-type I = usize;
+type I = Usize;
 
 #[derive(Clone, Copy, PartialEq)]
 struct PartNumber {
@@ -83,7 +87,7 @@ fn get_numbers(input: &Vec<Row>) -> Vec<PartNumber> {
 }
 
 /// Parses all symbols in a given row
-fn parse_symbols_in_row(input: &Row, y: I) -> Vec<Symbol> {
+fn parse_symbols_in_row(input: &Row, y: I, is_symbol: &impl Fn(char) -> bool) -> Vec<Symbol> {
     input.iter()
         .enumerate()
         .filter(|(_, &c)| is_symbol(c))
@@ -91,58 +95,112 @@ fn parse_symbols_in_row(input: &Row, y: I) -> Vec<Symbol> {
         .collect()
 }
 
-/// Parses all the Symbols, indexed by row
-fn get_symbols_by_row(input: &Vec<Row>) -> Vec<Vec<Symbol>> {
+/// Parses all the symbols, using the given predicate to decide which characters are symbols
+fn get_symbols(input: &Vec<Row>, is_symbol: &impl Fn(char) -> bool) -> Vec<Symbol> {
     input.iter()
         .enumerate()
-        .map(|(y, row)| parse_symbols_in_row(row, y))
+        .flat_map(|(y, row)| parse_symbols_in_row(row, y, is_symbol))
         .collect()
 }
 
-/// Checks if given number and symbol are adjacent
-fn is_adjacent(number: &PartNumber, symbol: &Symbol) -> bool {
-    let dy = number.y.abs_diff(symbol.y);
-    let dx = (number.min_x ..= number.max_x).map(|x| x.abs_diff(symbol.x)).min().unwrap();
-    dy <= 1 && dx <= 1
+/// Maps each cell covered by a number to the index of that number, so that
+/// the numbers next to a symbol can be looked up without scanning all numbers
+fn build_number_index(part_numbers: &[PartNumber]) -> HashMap<(I, I), usize> {
+    part_numbers.iter()
+        .enumerate()
+        .flat_map(|(i, num)| (num.min_x ..= num.max_x).map(move |x| ((x, num.y), i)))
+        .collect()
 }
 
-/// Parses the board into two lists: numbers and symbols
-fn parse(input: &str) -> (Vec<PartNumber>, Vec<Vec<Symbol>>) {
-    let rows: Vec<Row> = input.lines().map(|line| line.chars().collect()).collect();
-    (get_numbers(&rows), get_symbols_by_row(&rows))
+/// Gets the indices of all numbers adjacent to the given symbol. A number that
+/// covers multiple neighboring cells is only returned once.
+fn adjacent_numbers(symbol: &Symbol, index: &HashMap<(I, I), usize>) -> Vec<usize> {
+    (symbol.y.saturating_sub(1) ..= symbol.y+1)
+        .cartesian_product(symbol.x.saturating_sub(1) ..= symbol.x+1)
+        .filter_map(|(y, x)| index.get(&(x, y)).copied())
+        .unique()
+        .collect()
 }
 
-/// Part 1 solution: Find numbers with adjacent symbols
-pub fn part1(input: &str) -> i64 {
-    let (part_numbers, symbols) = parse(input);
-    let max_y = input.lines().count()-1;
+/// Splits the board into rows. This is the only place that splits lines,
+/// so all other functions agree on the number of rows.
+fn parse_rows(input: &str) -> Vec<Row> {
+    input.lines().map(|line| line.chars().collect()).collect()
+}
+
+/// The parsed board, with the adjacent numbers of each symbol computed once
+/// when it is parsed
+struct Schematic {
+    part_numbers: Vec<PartNumber>,
+    symbols: Vec<Symbol>,
+    /// Indices of the numbers adjacent to each symbol, keyed by symbol position (x, y)
+    adjacent: HashMap<(I, I), Vec<usize>>,
+}
+
+impl Schematic {
+    /// Parses the board, treating every character except '.' and digits as a symbol
+    fn parse(input: &str) -> Schematic {
+        Schematic::parse_with(input, is_symbol)
+    }
+
+    /// Parses the board, with a custom predicate for the characters that are symbols
+    fn parse_with(input: &str, is_symbol: impl Fn(char) -> bool) -> Schematic {
+        let rows = parse_rows(input);
+        let part_numbers = get_numbers(&rows);
+        let symbols = get_symbols(&rows, &is_symbol);
+
+        let index = build_number_index(&part_numbers);
+        let adjacent = symbols.iter()
+            .map(|s| ((s.x, s.y), adjacent_numbers(s, &index)))
+            .collect();
+
+        Schematic { part_numbers, symbols, adjacent }
+    }
+
+    /// Numbers adjacent to the given symbol
+    fn numbers_next_to(&self, symbol: &Symbol) -> Vec<&PartNumber> {
+        self.adjacent[&(symbol.x, symbol.y)].iter().map(|&i| &self.part_numbers[i]).collect()
+    }
 
-    let parts_with_adjacent_symbol = part_numbers.iter().filter(|num| {
-        let mut symbol_candidates = (&symbols[num.y.saturating_sub(1) ..= (num.y+1).min(max_y)]).iter().flatten();
-        symbol_candidates.any(|sym| is_adjacent(num, sym))
-    });
+    /// All numbers that have at least one adjacent symbol. Numbers next to
+    /// multiple symbols are only returned once.
+    fn part_numbers_with_symbols(&self) -> Vec<&PartNumber> {
+        self.adjacent.values()
+            .flatten()
+            .unique()
+            .sorted()
+            .map(|&i| &self.part_numbers[i])
+            .collect()
+    }
+
+    /// The two numbers of each star symbol with exactly two adjacent numbers
+    fn gears(&self) -> Vec<(&PartNumber, &PartNumber)> {
+        self.symbols.iter()
+            .filter(|s| s.value == '*')
+            .map(|s| self.numbers_next_to(s))
+            .filter(|n| n.len() == 2)
+            .map(|n| (n[0], n[1]))
+            .collect()
+    }
+}
 
-    parts_with_adjacent_symbol.map(|num| num.value).sum::<I>() as i64
+/// Part 1 solution: Find numbers with adjacent symbols
+pub fn part1(input: &str) -> i64 {
+    Schematic::parse(input)
+        .part_numbers_with_symbols()
+        .iter()
+        .map(|num| num.value)
+        .sum::<I>().as_i64()
 }
 
 /// Part 2 solution: Star symbols which have exactly two adjacent numbers
 pub fn part2(input: &str) -> i64 {
-    let (part_numbers, symbols) = parse(input);
-
-    let stars_with_two_nums = symbols.iter()
-        .flatten()
-        .filter(|s| s.value == '*')
-        .map(|s| {
-            part_numbers.iter()
-                .filter(|n| is_adjacent(n, s))
-                .collect::<Vec<_>>()
-        })
-        .filter(|n| { n.len() == 2 });
-
     // Multiply each two numbers, then sum the results
-    stars_with_two_nums
-        .map(|n| { n[0].value * n[1].value })
-        .sum::<I>() as i64
+    Schematic::parse(input)
+        .gears()
+        .iter()
+        .map(|(a, b)| a.value * b.value)
+        .sum::<I>().as_i64()
 }
 
 #[cfg(test)]
@@ -166,4 +224,81 @@ mod tests {
         assert_eq!(part1(example_input), 4361);
         assert_eq!(part2(example_input), 467835);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_shared_gear_numbers() {
+        // 34 is adjacent to both gears, and 123 touches the lower gear with all three digits
+        let example_input = "\
+            12*34*56\n\
+            ........\n\
+            .123....\n\
+            ..*.....\n\
+            ..4.....";
+
+        assert_eq!(part2(example_input), 12*34 + 34*56 + 123*4);
+    }
+
+    #[test]
+    fn test_numbers_in_corners() {
+        let example_input = "\
+            12....\n\
+            .*....\n\
+            ......\n\
+            ....#.\n\
+            ....34";
+
+        assert_eq!(part1(example_input), 12 + 34);
+    }
+
+    #[test]
+    fn test_symbol_on_last_line() {
+        let example_input = "\
+            ......\n\
+            .56...\n\
+            ..*78.";
+
+        assert_eq!(part1(example_input), 56 + 78);
+        assert_eq!(part2(example_input), 56 * 78);
+
+        // Same result with a trailing line ending
+        let example_input = format!("{}\n", example_input);
+        assert_eq!(part1(&example_input), 56 + 78);
+        assert_eq!(part2(&example_input), 56 * 78);
+    }
+
+    #[test]
+    fn test_symbol_next_to_three_numbers() {
+        let example_input = "\
+            1.2.\n\
+            .*..\n\
+            3..#\n\
+            ...4";
+
+        let schematic = Schematic::parse(example_input);
+        let star = schematic.symbols.iter().find(|s| s.value == '*').unwrap();
+        assert_eq!(schematic.numbers_next_to(star).iter().map(|n| n.value).sorted().collect_vec(), vec![1, 2, 3]);
+        assert_eq!(schematic.part_numbers_with_symbols().iter().map(|n| n.value).collect_vec(), vec![1, 2, 3, 4]);
+
+        // A star with three numbers is not a gear
+        assert!(schematic.gears().is_empty());
+        assert_eq!(part1(example_input), 1 + 2 + 3 + 4);
+        assert_eq!(part2(example_input), 0);
+    }
+
+    #[test]
+    fn test_parse_with_custom_symbols() {
+        let example_input = "\
+            1.2.\n\
+            .*..\n\
+            3..#\n\
+            ...4";
+
+        let schematic = Schematic::parse_with(example_input, |c| c == '#');
+        assert_eq!(schematic.symbols.iter().map(|s| s.value).collect_vec(), vec!['#']);
+        assert_eq!(schematic.part_numbers_with_symbols().iter().map(|n| n.value).collect_vec(), vec![4]);
+        assert!(schematic.gears().is_empty());
+
+        // The default predicate treats the star as a symbol, too
+        assert_eq!(Schematic::parse(example_input).symbols.len(), 2);
+    }
+}
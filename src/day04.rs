@@ -50,17 +50,19 @@ pub fn part2(input: &str) -> I {
     let cards = parse(input);
     let winning_counts = cards.iter().map(winning_count).collect::<Vec<_>>();
 
-    let mut card_counts = cards.iter().map(|_| 1).collect::<Vec<_>>();
+    // The counts double with each card in a long chain of winning cards,
+    // so we check for overflows instead of silently wrapping in release builds.
+    let mut card_counts: Vec<I> = cards.iter().map(|_| 1).collect();
 
-    let mut total_count = 0;
+    let mut total_count: I = 0;
 
     for i in 0..cards.len() {
         let count = card_counts[i];
         let winning = winning_counts[i];
-        total_count += count;
+        total_count = total_count.checked_add(count).expect("Total card count overflow");
         for j in i+1..=i+(winning as usize) {
             if j < card_counts.len() {
-                card_counts[j] += count;
+                card_counts[j] = card_counts[j].checked_add(count).expect("Card count overflow");
             }
         }
     }
@@ -85,4 +87,19 @@ mod tests {
         assert_eq!(part1(example_input), 13);
         assert_eq!(part2(example_input), 30);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_long_winning_chain() {
+        // Each card wins copies of all following cards, so card k (1-based) ends up
+        // with 2^(k-1) copies, and the total is 2^n - 1, which does not fit in 32 bits.
+        let n = 40;
+        let input = (1..=n).map(|k| {
+            let winning = n - k;
+            let numbers = (1..=winning).map(|num| num.to_string()).collect::<Vec<_>>().join(" ");
+            format!("Card {}: 100 {} | 101 {}", k, numbers, numbers)
+        }).collect::<Vec<_>>().join("\n");
+
+        assert_eq!(part2(&input), (1 << n) - 1);
+        assert_eq!(part2(&input), 1_099_511_627_775);
+    }
+}
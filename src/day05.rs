@@ -14,7 +14,9 @@ use crate::utils::*;
 type MapRange = (I, I);
 struct Map {
     ranges: Vec<(MapRange, MapRange)>,
-    name: String
+    /// Source and destination category, e.g. "seed" and "soil" for "seed-to-soil"
+    from: String,
+    to: String,
 }
 
 fn parse_range(input: &str) -> (MapRange, MapRange) {
@@ -28,15 +30,38 @@ fn parse_map(input: &Vec<String>) -> Vec<(MapRange, MapRange)> {
 }
 
 fn parse_maps(lines: Vec<String>) -> Vec<Map> {
-    lines
+    let maps = lines
         .split(|item| item.trim().len() == 0)
         .filter(|v| !v.is_empty())
         .map(|lines| {
             let lines_trimmed = lines.iter().map(|l| l.trim().to_string()).vec();
-            let name = lines_trimmed[0].trim().split(" ").next().unwrap().to_string();
-            Map { ranges: parse_map(&lines_trimmed[1..].to_vec()), name }
+            let name = lines_trimmed[0].trim().split(' ').next().unwrap();
+            let (from, to) = name.split_once("-to-").expect("Invalid map name");
+            Map { ranges: parse_map(&lines_trimmed[1..].to_vec()), from: from.to_string(), to: to.to_string() }
         })
-        .vec()
+        .vec();
+    order_maps(maps)
+}
+
+/// Orders the maps so that they form a chain (e.g. seed-to-soil, soil-to-fertilizer, ...
+/// up to humidity-to-location), independent of the order in the input.
+/// The chain starts at the only category that no map converts to.
+fn order_maps(mut maps: Vec<Map>) -> Vec<Map> {
+    let start = maps.iter()
+        .map(|map| map.from.clone())
+        .filter(|from| !maps.iter().any(|map| &map.to == from))
+        .exactly_one()
+        .unwrap_or_else(|_| panic!("The maps don't form a single chain"));
+
+    let mut ordered = vec![];
+    let mut category = start;
+    while let Some(i) = maps.iter().position(|map| map.from == category) {
+        let map = maps.remove(i);
+        category = map.to.clone();
+        ordered.push(map);
+    }
+    assert!(maps.is_empty(), "The maps don't form a single chain");
+    ordered
 }
 
 fn do_ranges_overlap((from_min, from_max): MapRange, (to_min, to_max): MapRange) -> bool {
@@ -89,7 +114,7 @@ fn get_min_location(seed_ranges: Vec<MapRange>, maps: Vec<Map>) -> I {
         //println!("Mapping {:?}", x);
         maps.iter().fold(vec![x], |from_ranges, map| {
             let mapped = from_ranges.map(|&range| apply_map_range(map, range)).flatten().vec();
-            //println!("{} mapped {:?} to {:?}", map.name, from_ranges, mapped);
+            //println!("{}-to-{} mapped {:?} to {:?}", map.from, map.to, from_ranges, mapped);
             mapped
         })
     }).flatten();
@@ -97,8 +122,14 @@ fn get_min_location(seed_ranges: Vec<MapRange>, maps: Vec<Map>) -> I {
     mapped_ranges.map(|(min, _max)| min).min().unwrap()
 }
 
+/// Splits the input into lines (the seeds, followed by the maps). Accepts CRLF
+/// line endings, so that no line ends with a '\r'.
+fn parse_lines(input: &str) -> Vec<String> {
+    lines(input).map(|l| l.to_string()).vec()
+}
+
 pub fn part1(input: &str) -> I {
-    let lines = input.split("\n").map(|l| l.to_string()).vec();
+    let lines = parse_lines(input);
 
     let seed_ranges = lines[0]
         .split_whitespace()
@@ -111,16 +142,21 @@ pub fn part1(input: &str) -> I {
     get_min_location(seed_ranges, maps)
 }
 
-pub fn part2(input: &str) -> I {
-    let lines = input.split("\n").map(|l| l.to_string()).vec();
-
-    let seed_ranges = lines[0]
+/// Parses the seeds line with the interpretation from part 2 (pairs of start and length)
+fn parse_seed_ranges(line: &str) -> Vec<MapRange> {
+    line
         .split_whitespace()
         .skip(1)
         .parse_i64()
-        .array_chunks::<2>()
+        .chunks2()
         .map(|c| (c[0], c[0] + c[1] - 1))
-        .vec();
+        .vec()
+}
+
+pub fn part2(input: &str) -> I {
+    let lines = parse_lines(input);
+
+    let seed_ranges = parse_seed_ranges(&lines[0]);
 
     let maps = parse_maps(lines[1..].to_vec());
     get_min_location(seed_ranges, maps)
@@ -129,6 +165,60 @@ pub fn part2(input: &str) -> I {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    /// Generates a random puzzle input with numbers in 0..domain: seed ranges
+    /// and a chain of maps, where the source ranges within each map don't overlap
+    fn random_input(rng: &mut StdRng, domain: I) -> String {
+        let seeds = (0..rng.gen_range(1..=3)).map(|_| {
+            let start = rng.gen_range(0..domain);
+            let length = rng.gen_range(1..=domain - start);
+            format!("{} {}", start, length)
+        }).join(" ");
+
+        let maps = (0..rng.gen_range(1..=4)).map(|i| {
+            // Random non-overlapping source ranges, from sorted cut points
+            let cuts = (0..rng.gen_range(2..=8)).map(|_| rng.gen_range(0..=domain)).sorted().dedup().vec();
+            let mut ranges = vec![];
+            for w in cuts.windows(2) {
+                if rng.gen_bool(0.7) {
+                    let (source, length) = (w[0], w[1] - w[0]);
+                    ranges.push(format!("{} {} {}", rng.gen_range(0..domain), source, length));
+                }
+            }
+            let ranges = ranges.join("\n");
+            format!("map{}-to-map{} map:\n{}", i, i + 1, ranges)
+        }).join("\n\n");
+
+        format!("seeds: {}\n\n{}", seeds, maps)
+    }
+
+    /// Reference for get_min_location: maps each seed number individually
+    fn brute_min_location(seed_ranges: &[MapRange], maps: &[Map]) -> I {
+        seed_ranges.iter()
+            .flat_map(|&(min, max)| min..=max)
+            .map(|seed| maps.iter().fold(seed, |n, map| {
+                match map.ranges.iter().find(|((from_min, from_max), _)| (*from_min..=*from_max).contains(&n)) {
+                    Some(&((from_min, _), (to_min, _))) => n - from_min + to_min,
+                    None => n,
+                }
+            }))
+            .min()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_random_maps_match_brute_force() {
+        let mut rng = StdRng::seed_from_u64(2023);
+        for _ in 0..500 {
+            let input = random_input(&mut rng, 100);
+            let lines = parse_lines(&input);
+            let seed_ranges = parse_seed_ranges(&lines[0]);
+            let maps = parse_maps(lines[1..].to_vec());
+
+            assert_eq!(part2(&input), brute_min_location(&seed_ranges, &maps), "Input:\n{}", input);
+        }
+    }
 
     #[test]
     fn test_example_input() {
@@ -170,4 +260,60 @@ mod tests {
             assert_eq!(part1(example_input), 35);
             assert_eq!(part2(example_input), 46);
         }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_crlf_line_endings() {
+        let input = "seeds: 79 14\r\n\r\nseed-to-soil map:\r\n50 98 2\r\n52 50 48";
+
+        let lines = parse_lines(input);
+        assert_eq!(lines, parse_lines(&input.replace("\r\n", "\n")));
+        assert_eq!(lines[0], "seeds: 79 14");
+        assert_eq!(lines[2], "seed-to-soil map:");
+        assert_eq!(part1(input), 14);
+    }
+
+    #[test]
+    fn test_maps_out_of_order() {
+        let example_input = "\
+            seeds: 79 14 55 13
+
+            humidity-to-location map:
+            60 56 37
+            56 93 4
+
+            soil-to-fertilizer map:
+            0 15 37
+            37 52 2
+            39 0 15
+
+            light-to-temperature map:
+            45 77 23
+            81 45 19
+            68 64 13
+
+            seed-to-soil map:
+            50 98 2
+            52 50 48
+
+            water-to-light map:
+            88 18 7
+            18 25 70
+
+            temperature-to-humidity map:
+            0 69 1
+            1 0 69
+
+            fertilizer-to-water map:
+            49 53 8
+            0 11 42
+            42 0 7
+            57 7 4";
+
+        assert_eq!(part1(example_input), 35);
+        assert_eq!(part2(example_input), 46);
+
+        let maps = parse_maps(lines(example_input).skip(1).map(|l| l.to_string()).vec());
+        assert_eq!(maps.iter().map(|map| map.from.as_str()).vec(), vec!["seed", "soil", "fertilizer", "water", "light", "temperature", "humidity"]);
+        assert_eq!(maps.last().unwrap().to, "location");
+    }
+}
@@ -15,14 +15,22 @@ use crate::utils::*;
 type Card = char;
 const CARD_ORDER: [char; 14] = ['A', 'K', 'Q', 'J', 'T', '9', '8', '7', '6', '5', '4', '3', '2', '!'];
 
+/// Maps each card to its rank
+type CardRanks = HashMap<Card, I>;
+
+/// Builds the rank lookup for the given card order (from highest to lowest card)
+fn card_ranks(order: &[Card]) -> CardRanks {
+    order.iter().rev().enumerate().map(|(rank, &card)| (card, rank as I)).collect()
+}
+
 /// Returns the rank of a card
-fn card_rank(card: Card) -> I {
-    CARD_ORDER.iter().rev().position(|&v| v == card).unwrap() as I
+fn card_rank(ranks: &CardRanks, card: Card) -> I {
+    ranks[&card]
 }
 
 /// Comparer for cards, uses card_rank
-fn compare_card((card, other_card): (Card, Card)) -> Ordering {
-    card_rank(card).cmp(&card_rank(other_card))
+fn compare_card(ranks: &CardRanks, (card, other_card): (Card, Card)) -> Ordering {
+    card_rank(ranks, card).cmp(&card_rank(ranks, other_card))
 }
 
 #[derive(Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Debug)]
@@ -65,21 +73,32 @@ fn detect_hand_type(cards: &Vec<Card>) -> HandType {
 
 type Hand = (HandType, Vec<Card>, I);
 
-fn parse_hand(input: &str) -> Hand {
-    let (cards_s, bid_s) = input.trim().split_whitespace().pair();
+/// Parses a hand with its bid, like "32T3K 765". Returns an error if the hand
+/// does not have exactly five cards, or contains an unknown card.
+fn parse_hand(input: &str) -> Result<Hand, String> {
+    let (cards_s, bid_s) = input.split_whitespace()
+        .collect_tuple()
+        .ok_or_else(|| format!("Expected cards and bid: {}", input.trim()))?;
 
     let cards = cards_s.chars().collect::<Vec<_>>();
+    if cards.len() != 5 {
+        return Err(format!("Expected five cards, found {}: {}", cards.len(), cards_s));
+    }
+    if let Some(card) = cards.iter().find(|card| !CARD_ORDER.contains(card)) {
+        return Err(format!("Unknown card '{}': {}", card, cards_s));
+    }
 
-    (detect_hand_type(&cards), cards, bid_s.parse::<I>().unwrap())
+    let bid = bid_s.parse::<I>().map_err(|_| format!("Invalid bid: {}", bid_s))?;
+    Ok((detect_hand_type(&cards), cards, bid))
 }
 
-/// Compares two hands according to the puzzle rules
-fn compare_hand((hand_type, hand_cards, _): &Hand, (other_type, other_cards, _): &Hand) -> Ordering {
+/// Compares two hands according to the puzzle rules, using the given card ranks
+fn compare_hand(ranks: &CardRanks, (hand_type, hand_cards, _): &Hand, (other_type, other_cards, _): &Hand) -> Ordering {
     match compare_hand_type(*hand_type, *other_type) {
         // If hand types are unequal, compare the cards pair by pair, find the first unequal ones, and return their ordering
         Ordering::Equal => hand_cards.iter()
             .zip(other_cards)
-            .map(|(c,d)| compare_card((*c, *d)))
+            .map(|(c,d)| compare_card(ranks, (*c, *d)))
             .find(|&ordering| ordering != Ordering::Equal)
             .unwrap_or(Ordering::Equal),
         x => x
@@ -106,12 +125,13 @@ fn play(input: &str) -> I {
     // Parse the hands
     let mut hands = input
         .split("\n")
-        .map(parse_hand)
+        .map(|line| parse_hand(line).expect("Invalid hand"))
         .map(|hand| max_joker_hand(&hand))
         .vec();
 
     // Sort the hands by rank
-    hands.sort_by(compare_hand);
+    let ranks = card_ranks(&CARD_ORDER);
+    hands.sort_by(|hand, other| compare_hand(&ranks, hand, other));
 
     // Compute the total winnings (bid * rank index)
     hands.iter()
@@ -146,4 +166,24 @@ mod tests {
         assert_eq!(part1(example_input), 6440);
         assert_eq!(part2(example_input), 5905);
     }
+
+    #[test]
+    fn test_parse_hand() {
+        assert_eq!(parse_hand("32T3K 765"), Ok((HandType::OnePair, vec!['3', '2', 'T', '3', 'K'], 765)));
+        assert!(parse_hand("32T3 765").unwrap_err().contains("five cards"));
+        assert!(parse_hand("32X3K 765").unwrap_err().contains("'X'"));
+    }
+
+    #[test]
+    fn test_card_ranks() {
+        let ranks = card_ranks(&CARD_ORDER);
+        assert_eq!(ranks.len(), CARD_ORDER.len());
+        for card in CARD_ORDER {
+            let expected = CARD_ORDER.iter().rev().position(|&v| v == card).unwrap() as I;
+            assert_eq!(card_rank(&ranks, card), expected);
+        }
+        assert_eq!(card_rank(&ranks, 'A'), 13);
+        assert_eq!(card_rank(&ranks, '2'), 1);
+        assert_eq!(card_rank(&ranks, '!'), 0);
+    }
 }
\ No newline at end of file
@@ -13,25 +13,30 @@
 ///   This does not work with the naive approach of just keeping track of the current position, the step count is very large.
 ///   Instead, we simulate the stepping for each starting node until we find a cycle
 ///   (i.e. we reach a node that we have already visited using the same index in the direction list).
-///   The solution is then the least common multiple of the cycle lengths for each starting node.
-///   This is not a general solution, but it turns out that each starting node only ever visits one target node,
-///   and there is a clean single cycle for each one (if we would pass multiple different goal nodes, this would be
-///   more complicated).
+///   For the puzzle input, each starting node only ever visits one target node, and there is a clean single cycle for
+///   each one, so the solution is the least common multiple of the cycle lengths. To also handle paths that pass
+///   multiple goal nodes (or reach goals before entering the cycle), we combine the goal offsets and cycle lengths of
+///   all starting nodes with the chinese remainder theorem.
 
 use crate::utils::*;
 type Map = HashMap<String, (String, String)>;
 
-fn parse(input: &str) -> (Vec<char>, Map) {
-    let mut lines = input.split("\n");
-    (
+/// Parses the directions and the map. Returns an error if a map line does not
+/// consist of exactly three node names (node, left, right).
+fn parse(input: &str) -> Result<(Vec<char>, Map), String> {
+    let mut lines = lines(input);
+    Ok((
         // First line contains the directions (char array)
         lines.next().unwrap().trim().chars().vec(),
         // Other lines contain the map (node -> (left, right))
-        Map::from_iter(lines.skip(1).map(|line| {
-            let v = parse::alphanums(line);
-            (v[0].to_string(), (v[1].to_string(), v[2].to_string()))
-        }))
-    )
+        lines.enumerate().skip(1).map(|(i, line)| {
+            let (node, left, right) = parse::alphanums(line)
+                .into_iter()
+                .collect_tuple()
+                .ok_or_else(|| format!("Expected node, left and right on line {}: '{}'", i + 2, line.trim()))?;
+            Ok((node, (left, right)))
+        }).collect::<Result<Map, String>>()?
+    ))
 }
 
 fn step<'a>(pos: &str, dir: char, map: &'a Map) -> &'a str {
@@ -44,7 +49,7 @@ fn step<'a>(pos: &str, dir: char, map: &'a Map) -> &'a str {
 }
 
 pub fn part1(input: &str) -> I {
-    let (directions, map) = parse(input);
+    let (directions, map) = parse(input).expect("Invalid input");
     let dircycle = directions.iter().cycle();
     let mut steps = 0;
     let mut pos = "AAA";
@@ -59,47 +64,102 @@ pub fn part1(input: &str) -> I {
     steps
 }
 
-pub fn part2(input: &str) -> I {
-    let (directions, map) = parse(input);
-
-    let starts = map.keys().filter(|&node| node.ends_with("A")).vec();
-
-    // For each starting position, find the cycle lengths for reaching target nodes
-    let cycles = starts.map(|&start| {
-        let mut cycle_lengths = Vec::<I>::new();
-        let dircycle = directions.iter().enumerate().cycle();
-        let mut steps = 0i64;
-        let mut position: &str = start;
-        let mut seen_states = Vec::<(&str, usize)>::new();
-
-        // Step through the map until we find a position that we have seen at the
-        // same direction index before.
-        for (dir_index, &dir) in dircycle {
-            position = step(position, dir, &map);
-
-            steps += 1;
-
-            if position.ends_with("Z") {
-                let current_state = (position as &str, dir_index);
-                if seen_states.contains(&current_state) {
-                    break;
-                } else {
-                    seen_states.push(current_state);
-                    cycle_lengths.push(steps);
-                    steps = 0;
-                }
+/// Path of a single ghost, which ends up in a cycle after mu steps. The cycle
+/// has a length of lambda steps.
+struct GhostPath<'a> {
+    /// Node after each step, for the steps 0..mu+lambda
+    nodes: Vec<&'a str>,
+    mu: I,
+    lambda: I,
+}
+
+impl<'a> GhostPath<'a> {
+    /// Walks from the start node until a (node, direction index) state repeats
+    fn find(start: &'a str, directions: &[char], map: &'a Map) -> GhostPath<'a> {
+        let mut seen_states = HashMap::<(&str, usize), I>::new();
+        let mut nodes = vec![];
+        let mut position = start;
+        for steps in 0.. {
+            let dir_index = steps as usize % directions.len();
+            if let Some(&mu) = seen_states.get(&(position, dir_index)) {
+                return GhostPath { nodes, mu, lambda: steps - mu };
             }
+            seen_states.insert((position, dir_index), steps);
+            nodes.push(position);
+            position = step(position, directions[dir_index], map);
+        }
+        unreachable!()
+    }
+
+    /// Node after the given number of steps
+    fn node_at(&self, steps: I) -> &str {
+        if steps < self.mu {
+            self.nodes[steps as usize]
+        } else {
+            self.nodes[(self.mu + (steps - self.mu) % self.lambda) as usize]
         }
+    }
 
-        cycle_lengths
-    });
+    fn is_goal_at(&self, steps: I) -> bool {
+        self.node_at(steps).ends_with('Z')
+    }
 
-    // Turns out that each starting node only ever visits one target node
-    // and there is a clean single cycle for each one.
-    // So we can just return the least common multiple of all the cycle lengths
-    cycles
-        .map(|lens| lens[0])
-        .fold(1i64, |a, b| lcm(a, b))
+    /// Steps at which a goal is reached before entering the cycle
+    fn transient_goals(&self) -> Vec<I> {
+        (1..self.mu).filter(|&steps| self.is_goal_at(steps)).vec()
+    }
+
+    /// Goals reached in the cycle, as (steps mod lambda, lambda)
+    fn cycle_goals(&self) -> Vec<(I, I)> {
+        (self.mu.max(1)..self.mu + self.lambda)
+            .filter(|&steps| self.is_goal_at(steps))
+            .map(|steps| (steps % self.lambda, self.lambda))
+            .vec()
+    }
+}
+
+/// Part 2: Find the number of steps until all ghosts are on goal nodes at the
+/// same time. Returns an error if there is no such step.
+pub fn try_part2(input: &str) -> Result<I, String> {
+    let (directions, map) = parse(input)?;
+
+    let starts = map.keys().filter(|&node| node.ends_with("A")).sorted().vec();
+    let paths = starts.iter().map(|start| GhostPath::find(start, &directions, &map)).vec();
+
+    for (start, path) in starts.iter().zip(&paths) {
+        if path.transient_goals().is_empty() && path.cycle_goals().is_empty() {
+            return Err(format!("The ghost starting at {} never reaches a goal node", start));
+        }
+    }
+
+    // A goal reached before a ghost enters its cycle is only reached once, so
+    // just check if all other ghosts are on a goal node at that step.
+    let transient_solution = paths.iter()
+        .flat_map(|path| path.transient_goals())
+        .filter(|&steps| paths.iter().all(|path| path.is_goal_at(steps)))
+        .min();
+
+    // Otherwise, all ghosts must be in their cycles. Each ghost can be at any
+    // of the goals in its cycle, so try all combinations, and combine the
+    // cycles with the chinese remainder theorem.
+    let min_steps = paths.iter().map(|path| path.mu.max(1)).max().unwrap_or(1);
+    let cycle_solution = paths.iter()
+        .map(|path| path.cycle_goals())
+        .multi_cartesian_product()
+        .map(|goals| math::crt(&goals))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| format!("The ghost cycles can't be combined: {}", error))?
+        .into_iter()
+        .flatten()
+        .map(|(r, m)| if r >= min_steps { r } else { r + (min_steps - r + m - 1) / m * m })
+        .min();
+
+    transient_solution.into_iter().chain(cycle_solution).min()
+        .ok_or_else(|| "The ghosts are never on goal nodes at the same time".to_string())
+}
+
+pub fn part2(input: &str) -> I {
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
@@ -118,6 +178,20 @@ mod tests {
         assert_eq!(part1(example_input), 6);
     }
 
+    #[test]
+    fn test_parse_error() {
+        let input = "\
+            LLR
+
+            AAA = (BBB, BBB)
+            BBB = (AAA)
+            ZZZ = (ZZZ, ZZZ)";
+
+        let error = parse(input).unwrap_err();
+        assert!(error.contains("line 4"));
+        assert!(error.contains("BBB = (AAA)"));
+    }
+
     #[test]
     fn test_example_input_2() {
         let example_input = "\
@@ -134,4 +208,71 @@ mod tests {
 
         assert_eq!(part2(example_input), 6);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_multiple_goals_per_cycle() {
+        // The first ghost reaches a goal after every odd number of steps
+        // (alternating between 11Z and 12Z), the second one after 2, 5, 8, ...
+        let example_input = "\
+            L
+
+            11A = (11Z, 11Z)
+            11Z = (11B, 11B)
+            11B = (12Z, 12Z)
+            12Z = (11C, 11C)
+            11C = (11Z, 11Z)
+            22A = (22B, 22B)
+            22B = (22Z, 22Z)
+            22Z = (22C, 22C)
+            22C = (22B, 22B)";
+
+        assert_eq!(part2(example_input), 5);
+    }
+
+    #[test]
+    fn test_no_common_goal() {
+        // The first ghost is on goals at odd steps, the second one at even steps
+        let example_input = "\
+            L
+
+            11A = (11Z, 11Z)
+            11Z = (11B, 11B)
+            11B = (11Z, 11Z)
+            22A = (22B, 22B)
+            22B = (22Z, 22Z)
+            22Z = (22B, 22B)";
+
+        assert!(try_part2(example_input).unwrap_err().contains("never on goal nodes at the same time"));
+    }
+
+    #[test]
+    fn test_cycles_overflow() {
+        // One ghost for each prime up to 53, each one reaches its goal at the
+        // end of a cycle with the length of the prime. The product of the
+        // primes does not fit into an i64.
+        let primes = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53];
+        let mut input = "L\n\n".to_string();
+        for p in primes {
+            input += &format!("{p}A = ({p}C1, {p}C1)\n");
+            for i in 1..p - 1 {
+                input += &format!("{p}C{i} = ({p}C{next}, {p}C{next})\n", next = i + 1);
+            }
+            input += &format!("{p}C{last} = ({p}Z, {p}Z)\n{p}Z = ({p}C1, {p}C1)\n", last = p - 1);
+        }
+
+        let error = try_part2(input.trim()).unwrap_err();
+        assert!(error.contains("does not fit into an i64"), "{}", error);
+    }
+
+    #[test]
+    fn test_crlf_line_endings() {
+        let example_input = "\
+            LLR\r
+            \r
+            AAA = (BBB, BBB)\r
+            BBB = (AAA, ZZZ)\r
+            ZZZ = (ZZZ, ZZZ)";
+
+        assert_eq!(part1(example_input), 6);
+    }
+}
@@ -12,19 +12,22 @@
 use crate::utils::*;
 
 fn extrapolate(values: Vec<i64>) -> (I, I) {
-    // When all values are zero, return zeroes
+    // When there are no values or all values are zero, return zeroes
     if values.iter().all(|&x| x == 0) {
         (0, 0)
+    } else if let [value] = values[..] {
+        // A single value is a constant sequence
+        (value, value)
     } else {
         // Otherwise get the differences between each pair of values and extrapolate from them
-        let diffs = values.iter().map_windows(|[&a, &b]| b - a).vec();
+        let diffs = values.iter().windows2().map(|(a, b)| b - a).vec();
         let (l, r) = extrapolate(diffs);
         (values[0] - l, values[values.len()-1] + r)
     }
 }
 
 fn solution(input: &str) -> (I,I) {
-    input.split("\n")
+    lines(input)
         // Parse each line into a list of vectors
         .map(|l| l.split_whitespace().parse_i64().vec())
         // Extrapolate in both directions
@@ -55,4 +58,11 @@ mod tests {
 
         assert_eq!(solution(example_input), (2, 114));
     }
+
+    #[test]
+    fn test_short_sequences() {
+        assert_eq!(extrapolate(vec![]), (0, 0));
+        assert_eq!(extrapolate(vec![5]), (5, 5));
+        assert_eq!(extrapolate(vec![5, 5]), (5, 5));
+    }
 }
\ No newline at end of file
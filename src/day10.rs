@@ -9,8 +9,8 @@
 
 use crate::{utils::*, vec2::Vec2};
 
-type Map = HashMap::<Vec2, Pipe>;
-type Pipe = (char, Vec2);
+type Map = HashMap::<Vec2, Cell>;
+type Cell = (Pipe, Vec2);
 const DIRS: [Vec2;4] = [
     Vec2(0,1),
     Vec2(0,-1),
@@ -18,9 +18,59 @@ const DIRS: [Vec2;4] = [
     Vec2(-1,0),
 ];
 
-fn parse_pipes(input: &str) -> Vec<Pipe> {
-    input.split("\n").enumerate().map(|(y, line)| {
-        line.trim().chars().enumerate().map(move |(x, shape)| {
+/// Pipe shapes, named by the directions they connect (north is up)
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Pipe { NS, EW, NE, NW, SW, SE, Start, Ground }
+
+impl Pipe {
+    fn from_char(c: char) -> Option<Pipe> {
+        match c {
+            '|' => Some(Pipe::NS),
+            '-' => Some(Pipe::EW),
+            'L' => Some(Pipe::NE),
+            'J' => Some(Pipe::NW),
+            '7' => Some(Pipe::SW),
+            'F' => Some(Pipe::SE),
+            'S' => Some(Pipe::Start),
+            '.' => Some(Pipe::Ground),
+            _ => None,
+        }
+    }
+
+    /// Gets the two directions that a pipe can connect to.
+    /// The ground doesn't connect anywhere, and the start is replaced by its
+    /// actual shape when parsing (see infer_start_shape).
+    fn directions(self) -> [Vec2;2] {
+        match self {
+            Pipe::NS => [(0,1),(0,-1)],
+            Pipe::EW => [(1,0),(-1,0)],
+            Pipe::NE => [(1,0),(0,-1)],
+            Pipe::NW => [(-1,0),(0,-1)],
+            Pipe::SW => [(-1,0),(0,1)],
+            Pipe::SE => [(1,0),(0,1)],
+            Pipe::Start | Pipe::Ground => [(0,0),(0,0)],
+        }.map(|p| p.into())
+    }
+
+    /// Box drawing character for printing the map
+    fn to_box_char(self) -> char {
+        match self {
+            Pipe::NS => '│',
+            Pipe::EW => '─',
+            Pipe::NE => '└',
+            Pipe::NW => '┘',
+            Pipe::SW => '┐',
+            Pipe::SE => '┌',
+            Pipe::Start => 'S',
+            Pipe::Ground => ' ',
+        }
+    }
+}
+
+fn parse_pipes(input: &str) -> Vec<Cell> {
+    lines(input).enumerate().map(|(y, line)| {
+        line.trim().chars().enumerate().map(move |(x, c)| {
+            let shape = Pipe::from_char(c).unwrap_or_else(|| panic!("Invalid pipe: {}", c));
             (shape, Vec2(x as I, y as I))
         })
     }).flatten().collect()
@@ -28,44 +78,53 @@ fn parse_pipes(input: &str) -> Vec<Pipe> {
 
 fn parse(input: &str) -> (Map, Vec2) {
     // Parse the map into a hashmap of (shape, position) tuples, indexed by position
-    let map = HashMap::<Vec2, Pipe>::from_iter(
+    let mut map = HashMap::<Vec2, Cell>::from_iter(
         parse_pipes(input)
         .map(|&(shape, pos)| (pos, (shape, pos)))
     );
     // Find the start position
-    let start = map.values().find(|&&(shape, _)| shape == 'S').unwrap().1;
+    let start = map.values().find(|&&(shape, _)| shape == Pipe::Start).unwrap().1;
+
+    // Replace the start tile with its actual pipe shape, so that it connects like any other pipe
+    let start_shape = infer_start_shape(&map, start).expect("Can't infer the shape of the start tile");
+    map.insert(start, (start_shape, start));
     (map, start)
 }
 
-// Gets the two directions that a pipe can connect to
-fn pipe_directions(shape: char) -> [Vec2;2] {
-    match shape {
-        '|' => [(0,1),(0,-1)],
-        '-' => [(1,0),(-1,0)],
-        'L' => [(1,0),(0,-1)],
-        'J' => [(-1,0),(0,-1)],
-        '7' => [(-1,0),(0,1)],
-        'F' => [(1,0),(0,1)],
-        'S' => [(0,0),(0,0)],
-        _   => [(0,0),(0,0)],
-    }.map(|p| p.into())
+/// Infers the pipe shape of the start tile from the neighbors that connect to it.
+/// Returns None unless exactly two neighbors connect to the start.
+fn infer_start_shape(map: &Map, start: Vec2) -> Option<Pipe> {
+    let connected = DIRS.iter()
+        .filter(|&&dir| map.get(&(start + dir)).is_some_and(|&neighbor| can_walk(start, neighbor)))
+        .map(|&dir| (dir.0, dir.1))
+        .vec();
+
+    match connected[..] {
+        [(0,1), (0,-1)] => Some(Pipe::NS),
+        [(1,0), (-1,0)] => Some(Pipe::EW),
+        [(0,-1), (1,0)] => Some(Pipe::NE),
+        [(0,-1), (-1,0)] => Some(Pipe::NW),
+        [(0,1), (-1,0)] => Some(Pipe::SW),
+        [(0,1), (1,0)] => Some(Pipe::SE),
+        _ => None,
+    }
 }
 
 // Checks if a pipe can connect to a given incoming position
-fn can_walk(from_pos: Vec2, (to_shape, to_pos): Pipe) -> bool {
-    let incoming_dirs = pipe_directions(to_shape);
+fn can_walk(from_pos: Vec2, (to_shape, to_pos): Cell) -> bool {
+    let incoming_dirs = to_shape.directions();
     let possible_incoming_positions = incoming_dirs.map(|p| p + to_pos);
     possible_incoming_positions.contains(&from_pos)
 }
 
 // Finds the loop in the map, returns the path and the furthest distance from the start
 fn find_loop_and_distance(map: Map, start: Vec2) -> (Vec<Vec2>, I) {
-    // From the start position, try to walk into all directions
-    let mut paths = DIRS.iter()
+    // From the start position, walk into both directions of the start pipe
+    let mut paths = map[&start].0.directions().iter()
         .map(|&dir| vec![start+dir])
-        .filter(|p| map.contains_key(&p[0]) && can_walk(start, map[&p[0]])).vec();
+        .vec();
 
-    // Expand the four paths until we find a position that is already on a different path.
+    // Expand the two paths until we find a position that is already on a different path.
     // Then those two paths form the loop, and that position is the furthest point.
     loop {
         for i in 0..paths.len() {
@@ -75,9 +134,9 @@ fn find_loop_and_distance(map: Map, start: Vec2) -> (Vec<Vec2>, I) {
                 let (shape, _) = map[&pos];
                 
                 // Try to expand the path
-                let maybe_next_pos = pipe_directions(shape).iter().map(|&dir| pos + dir).find(|new_pos| {
+                let maybe_next_pos = shape.directions().iter().map(|&dir| pos + dir).find(|new_pos| {
                     match map.get(new_pos) {
-                        Some(&next_pipe) => can_walk(pos, next_pipe) && !path.contains(&next_pipe.1),
+                        Some(&next_pipe) => can_walk(pos, next_pipe) && next_pipe.1 != start && !path.contains(&next_pipe.1),
                         _ => false,
                     }
                 });
@@ -141,14 +200,9 @@ fn print_map(map: &HashMap<Vec2, Side>, pipemap: &Map) {
         for x in 0..width {
             let x = match map[&Vec2(x,y)] {
                 Side::Path => match pipemap[&Vec2(x,y)] {
-                    ('-', _) => "─".bright_blue(),
-                    ('|', _) => "│".bright_blue(),
-                    ('L', _) => "└".bright_blue(),
-                    ('J', _) => "┘".bright_blue(),
-                    ('7', _) => "┐".bright_blue(),
-                    ('F', _) => "┌".bright_blue(),
-                    ('S', _) => "S".on_yellow(),
-                    _ => unreachable!(),
+                    (Pipe::Start, _) => "S".on_yellow(),
+                    (Pipe::Ground, _) => unreachable!(),
+                    (shape, _) => shape.to_box_char().to_string().bright_blue(),
                 },
                 Side::Left => "L".on_red(),
                 Side::Right => "R".on_green(),
@@ -184,7 +238,7 @@ pub fn part2(input: &str) -> I {
             (l+from_pos, Side::Left),
             (r+from_pos, Side::Right),
         ] {
-            if let Some(value) = map.get_mut(&pos) && *value == Side::Unknown {
+            if let Some(value @ Side::Unknown) = map.get_mut(&pos) {
                 *value = side;
             }
         }
@@ -294,4 +348,46 @@ mod tests {
 
         assert_eq!(part2(example_input), 4);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_pipe() {
+        assert_eq!("|-LJ7FS.".chars().map(Pipe::from_char).vec(), vec![
+            Some(Pipe::NS), Some(Pipe::EW), Some(Pipe::NE), Some(Pipe::NW),
+            Some(Pipe::SW), Some(Pipe::SE), Some(Pipe::Start), Some(Pipe::Ground),
+        ]);
+        assert_eq!(Pipe::from_char('x'), None);
+
+        assert_eq!(Pipe::NS.directions(), [Vec2(0,1), Vec2(0,-1)]);
+        assert_eq!(Pipe::NE.directions(), [Vec2(1,0), Vec2(0,-1)]);
+        assert_eq!(Pipe::SW.directions(), [Vec2(-1,0), Vec2(0,1)]);
+        assert_eq!(Pipe::Ground.directions(), [Vec2(0,0), Vec2(0,0)]);
+        assert_eq!(Pipe::SE.to_box_char(), '┌');
+    }
+
+    #[test]
+    fn test_infer_start_shape() {
+        let start_shape = |input: &str| {
+            let (map, start) = parse(input);
+            map[&start].0
+        };
+
+        assert_eq!(start_shape(".|.\n.S.\n.|."), Pipe::NS);
+        assert_eq!(start_shape("...\n-S-\n..."), Pipe::EW);
+        assert_eq!(start_shape(".|.\n.S-\n..."), Pipe::NE);
+        assert_eq!(start_shape(".|.\n-S.\n..."), Pipe::NW);
+        assert_eq!(start_shape("...\n-S.\n.|."), Pipe::SW);
+        assert_eq!(start_shape("...\n.S-\n.|."), Pipe::SE);
+
+        // Neighbors that don't point at the start are ignored
+        assert_eq!(start_shape(".L.\n|S-\n.|."), Pipe::SE);
+
+        // Ambiguous or unconnected start tiles can't be inferred
+        let infer = |input: &str| {
+            let map: Map = parse_pipes(input).map(|&(shape, pos)| (pos, (shape, pos))).collect();
+            infer_start_shape(&map, Vec2(1, 1))
+        };
+        assert_eq!(infer(".|.\n-S-\n.|."), None);
+        assert_eq!(infer("...\n.S.\n..."), None);
+        assert_eq!(infer(".|.\n.S.\n.|."), Some(Pipe::NS));
+    }
+}
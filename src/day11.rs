@@ -32,9 +32,9 @@ fn parse(input: &str) -> (Map, Vec2) {
         .vec();
 
     // Get the max x and y values
-    let max = map.iter().fold((0, 0), |(max_x, max_y), &Vec2(x, y)| (max_x.max(x), max_y.max(y)));
+    let (_, max) = bounds(map.iter().copied());
 
-    (map, max.into())
+    (map, max)
 }
 
 // Expand the row at the given y position by factor insert_len
@@ -17,11 +17,9 @@
 /// I ended up with a dynamic programming approach. It is described in full detail
 /// below.
 
-use memoize::memoize;
-
 use crate::utils::*;
 
-type I = usize;
+type I = Usize;
 
 #[derive(Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Debug, Hash)]
 enum Spring {
@@ -110,16 +108,64 @@ fn solve_row((row, constraints): Row) -> I {
     .sum()
 }
 
+/// Recursive alternative to solve_row, counting the arrangements of the given
+/// groups in the given springs. This is easier to follow (and to test on
+/// sub-problems) than the DP above:
+///  - Either the first spring is operational, and we solve the rest of the row,
+///  - or the first group starts at the first spring, and we solve the rest of
+///    the row after the group (and the operational spring following it) for
+///    the remaining groups.
+/// The cache maps (remaining springs, remaining groups) to the number of
+/// arrangements, so it must only be reused for the same row.
+/// The solution uses the DP, this is only used as a reference in the tests.
+#[cfg(test)]
+fn count_arrangements(springs: &[Spring], groups: &[I], cache: &mut HashMap<(I, I), I>) -> I {
+    let key = (springs.len(), groups.len());
+    if let Some(&count) = cache.get(&key) {
+        return count;
+    }
+
+    let count = match (springs.first(), groups.first()) {
+        // No groups left: only valid if there are no more broken springs
+        (_, None) => if springs.iter().all(|&s| s != Spring::Broken) { 1 } else { 0 },
+        // Groups left, but no springs
+        (None, Some(_)) => 0,
+        (Some(&first), Some(&group_size)) => {
+            let mut count = 0;
+
+            // Can the first spring be operational?
+            if first != Spring::Broken {
+                count += count_arrangements(&springs[1..], groups, cache);
+            }
+
+            // Can the first group be placed at the start?
+            // (it must fit, and must not be followed by a broken spring)
+            if springs.len() >= group_size
+                && springs[..group_size].iter().all(|&s| s != Spring::Operational)
+                && springs.get(group_size) != Some(&Spring::Broken)
+            {
+                let rest = &springs[(group_size + 1).min(springs.len())..];
+                count += count_arrangements(rest, &groups[1..], cache);
+            }
+
+            count
+        }
+    };
+
+    cache.insert(key, count);
+    count
+}
+
 pub fn part1(input: &str) -> i64 {
     parse(input, 1)
         .map(|row| solve_row(row.clone()))
-        .sum::<I>() as i64
+        .sum::<I>().as_i64()
 }
 
 pub fn part2(input: &str) -> i64 {
     parse(input, 5)
         .map(|row| solve_row(row.clone()))
-        .sum::<I>() as i64
+        .sum::<I>().as_i64()
 }
 
 
@@ -127,6 +173,27 @@ pub fn part2(input: &str) -> i64 {
 mod tests {
     use super::*;
 
+    /// Counts the arrangements by trying all assignments of the unknown
+    /// springs. Exponential in the number of unknown springs, so only usable
+    /// for small rows, but simple enough to serve as a reference for the DP.
+    fn brute_count(springs: &[Spring], groups: &[I]) -> I {
+        let unknown = springs.iter().positions(|&s| s == Spring::Unknown).vec();
+        (0..1u64 << unknown.len()).filter(|assignment| {
+            let mut assigned = springs.to_vec();
+            for (bit, &pos) in unknown.iter().enumerate() {
+                assigned[pos] = if assignment & (1 << bit) != 0 { Spring::Broken } else { Spring::Operational };
+            }
+
+            // Lengths of the groups of consecutive broken springs
+            let assigned_groups = assigned
+                .split(|&s| s == Spring::Operational)
+                .map(|group| group.len())
+                .filter(|&len| len > 0)
+                .vec();
+            assigned_groups == groups
+        }).count()
+    }
+
     #[test]
     fn test_example_input() {
         let example_input = "\
@@ -190,6 +257,45 @@ mod tests {
         assert_eq!(part1(example_input), 3);
     }
 
+    #[test]
+    fn test_count_arrangements() {
+        let example_input = "\
+        ???.### 1,1,3
+        .??..??...?##. 1,1,3
+        ?#?#?#?#?#?#?#? 1,3,1,6
+        ????.#...#... 4,1,1
+        ????.######..#####. 1,6,5
+        ?###???????? 3,2,1";
+
+        for multiply in [1, 5] {
+            for (springs, constraints) in parse(example_input, multiply) {
+                let expected = solve_row((springs.clone(), constraints.clone()));
+                assert_eq!(count_arrangements(&springs, &constraints, &mut HashMap::new()), expected);
+            }
+        }
+
+        let (springs, constraints) = parse_row("?###???????? 3,2,1", 1);
+        assert_eq!(count_arrangements(&springs, &constraints, &mut HashMap::new()), 10);
+    }
+
+    #[test]
+    fn test_brute_count() {
+        let example_input = "\
+        ???.### 1,1,3
+        .??..??...?##. 1,1,3
+        ?#?#?#?#?#?#?#? 1,3,1,6
+        ????.#...#... 4,1,1
+        ????.######..#####. 1,6,5
+        ?###???????? 3,2,1
+        ??.??.?? 1,1
+        ???? 1,1";
+
+        for (springs, constraints) in parse(example_input, 1) {
+            let expected = brute_count(&springs, &constraints);
+            assert_eq!(solve_row((springs, constraints)), expected);
+        }
+    }
+
     #[test]
     fn test_solver_1() {
         assert_eq!(solve_row(parse("? 1", 1)[0].clone()), 1);
@@ -15,8 +15,7 @@ use crate::utils::*;
 type Map = Vec<Vec<char>>;
 
 fn parse(input: &str) -> Vec<Map> {
-    input
-        .split("\n")
+    lines(input)
         .map(|line| line.trim())
         .vec()
         .split(|&line| line.len() == 0)
@@ -26,84 +25,76 @@ fn parse(input: &str) -> Vec<Map> {
         .vec()
 }
 
-// Checks if row Y is a reflection line
-fn is_reflected(map: &Map, y: I) -> bool {
+// Counts the cells that differ between the rows that are mirrored at the
+// reflection line below row Y
+fn count_reflection_diffs(map: &Map, y: I) -> usize {
     let up = (0 ..= (y as usize)).rev();
-    let down = ((y as usize)+1..map.len());
+    let down = (y as usize)+1..map.len();
 
-    up.zip(down).find(|&(y1, y2)| map[y1] != map[y2]).is_none()
+    up.zip(down)
+        .map(|(y1, y2)| map[y1].iter().zip(&map[y2]).filter(|(a, b)| a != b).count())
+        .sum()
 }
 
-// Flips rows and columns
-fn transpose(map: &Map) -> Map {
-    let (w, h) = (map[0].len(), map.len());
-    (0..w).map(|x| {
-        (0..h).map(|y| {
-            map[y][x]
-        }).vec()
-    }).vec()
+// Counts the cells that differ between the columns that are mirrored at the
+// reflection line right of column X
+fn count_reflection_diffs_col(map: &Map, x: I) -> usize {
+    map.iter()
+        .map(|row| {
+            let left = (0 ..= (x as usize)).rev();
+            let right = (x as usize)+1..row.len();
+            left.zip(right).filter(|&(x1, x2)| row[x1] != row[x2]).count()
+        })
+        .sum()
 }
 
-// Returns the first reflection row != ignore_y, or None
-fn try_find_reflection_row(map: &Map, ignore_y: I) -> Option<I> {
-    (0..(map.len() as I)-1).find(|&y| (y+1) != ignore_y && is_reflected(map, y)).and_then(|y| Some(y+1))
+// Checks if column X is a reflection line, comparing the columns in place.
+// Unlike count_reflection_diffs_col, this stops at the first differing cell.
+fn is_reflected_col(map: &Map, x: I) -> bool {
+    map.iter().all(|row| {
+        let left = (0 ..= (x as usize)).rev();
+        let right = (x as usize)+1..row.len();
+        left.zip(right).all(|(x1, x2)| row[x1] == row[x2])
+    })
 }
 
-// Returns the first reflection column != ignore_x, or None
-fn try_find_reflection_col(map: &Map, ignore_x: I) -> Option<I> {
-    try_find_reflection_row(&transpose(map), ignore_x)
+// Returns the first reflection row that has exactly the given number of
+// differing cells, or None
+fn try_find_reflection_row(map: &Map, diffs: usize) -> Option<I> {
+    (0..(map.len() as I)-1).find(|&y| count_reflection_diffs(map, y) == diffs).map(|y| y+1)
 }
 
-pub fn part1(input: &str) -> I {
-    let maps = parse(input);
-    // Find the reflection lines and sum the indices (times 100 for rows)
-    maps.iter()
-        .map(|pattern| {
-            try_find_reflection_row(pattern, -1)
-                .and_then(|row| Some(row*100))
-                .or_else(|| try_find_reflection_col(pattern, -1))
-                .unwrap()
+// Returns the first reflection column that has exactly the given number of
+// differing cells, or None
+fn try_find_reflection_col(map: &Map, diffs: usize) -> Option<I> {
+    (0..(map[0].len() as I)-1)
+        .find(|&x| match diffs {
+            0 => is_reflected_col(map, x),
+            _ => count_reflection_diffs_col(map, x) == diffs,
         })
-        .sum()
+        .map(|x| x+1)
 }
 
-// Returns all possible smudge variations of a map
-fn iter_smudges(map: &Map) -> impl Iterator<Item=Map> + '_ {
-    let (w, h) = (map[0].len(), map.len());
-    // For each cell, return a map copy with that cell flipped
-    (0..w).map(move |x| {
-        (0..h).map(move |y| {
-            // Copy the map, flip the cell at x,y, and return it
-            let mut smudge_copy = map.clone();
-            smudge_copy[y][x] = match smudge_copy[y][x] {
-                '#' => '.',
-                '.' => '#',
-                _ => unreachable!()
-            };
-
-            smudge_copy
-        })
-    }).flatten()
+// Finds the reflection line with the given number of differing cells
+// and returns its index (times 100 for rows)
+fn reflection_score(map: &Map, diffs: usize) -> I {
+    try_find_reflection_row(map, diffs)
+        .map(|row| row*100)
+        .or_else(|| try_find_reflection_col(map, diffs))
+        .unwrap()
+}
+
+pub fn part1(input: &str) -> I {
+    let maps = parse(input);
+    // Find the perfect reflection lines and sum the scores
+    maps.iter().map(|pattern| reflection_score(pattern, 0)).sum()
 }
 
 pub fn part2(input: &str) -> I {
     let maps = parse(input);
-    // Find the reflection lines after flipping a smudge cell, then same as part 1.
-    maps.iter()
-        .map(|pattern| {
-            // Find the old reflection row and column (or None)
-            let old_reflection_row = try_find_reflection_row(pattern, -1).unwrap_or(-1);
-            let old_reflection_col = try_find_reflection_col(pattern, -1).unwrap_or(-1);
-
-            // Iter over all smudges, search reflection lines for each, and return the first one
-            // that is not None
-            iter_smudges(pattern).map(|smudged_map| {
-                try_find_reflection_row(&smudged_map, old_reflection_row)
-                    .and_then(|row| Some(row*100))
-                    .or_else(|| try_find_reflection_col(&smudged_map, old_reflection_col))
-            }).flatten().next().unwrap()
-        })
-        .sum()
+    // The smudge is the only cell that breaks the new reflection, so we look
+    // for the reflection lines with exactly one differing cell.
+    maps.iter().map(|pattern| reflection_score(pattern, 1)).sum()
 }
 
 #[cfg(test)]
@@ -166,28 +157,64 @@ mod tests {
     fn test_row_detection() {
         let map = parse("#..#")[0].to_owned();
 
-        assert_eq!(try_find_reflection_row(&map, -1), None);
-        assert_eq!(try_find_reflection_col(&map, -1), Some(2));
+        assert_eq!(try_find_reflection_row(&map, 0), None);
+        assert_eq!(try_find_reflection_col(&map, 0), Some(2));
     }
 
     #[test]
-    fn test_smudge_detection() {
-        {
-            let map = vec![vec!['.', '.']];
-            let smudge_options = iter_smudges(&map).vec();
-            assert_eq!(smudge_options, vec![
-                vec![vec!['#', '.']],
-                vec![vec!['.', '#']]
-            ]);
-        }
+    fn test_reflection_diffs() {
+        let maps = parse("\
+        #.##..##.
+        ..#.##.#.
+        ##......#
+        ##......#
+        ..#.##.#.
+        ..##..##.
+        #.#.##.#.
 
-        {
-            let map = vec![vec!['.'], vec!['.']];
-            let smudge_options = iter_smudges(&map).vec();
-            assert_eq!(smudge_options, vec![
-                vec![vec!['#'], vec!['.']],
-                vec![vec!['.'], vec!['#']]
-            ]);
+        #...##..#
+        #....#..#
+        ..##..###
+        #####.##.
+        #####.##.
+        ..##..###
+        #....#..#");
+
+        let diffs = |map: &Map| (0..(map.len() as I)-1).map(|y| count_reflection_diffs(map, y)).vec();
+        assert_eq!(diffs(&maps[0]), vec![5, 13, 1, 15, 10, 5]);
+        assert_eq!(diffs(&transpose(&maps[0])), vec![2, 11, 13, 16, 0, 11, 8, 7]);
+        assert_eq!(diffs(&maps[1]), vec![1, 13, 19, 0, 12, 6]);
+        assert_eq!(diffs(&transpose(&maps[1])), vec![3, 7, 3, 17, 11, 15, 2, 5]);
+    }
+
+    #[test]
+    fn test_column_reflection() {
+        let maps = parse("\
+        #.##..##.
+        ..#.##.#.
+        ##......#
+        ##......#
+        ..#.##.#.
+        ..##..##.
+        #.#.##.#.
+
+        #...##..#
+        #....#..#
+        ..##..###
+        #####.##.
+        #####.##.
+        ..##..###
+        #....#..#");
+
+        // Comparing the columns in place must give the same result as comparing
+        // the rows of the transposed map
+        for map in &maps {
+            let transposed = transpose(map);
+            for x in 0..(map[0].len() as I)-1 {
+                assert_eq!(is_reflected_col(map, x), count_reflection_diffs(&transposed, x) == 0);
+                assert_eq!(count_reflection_diffs_col(map, x), count_reflection_diffs(&transposed, x));
+            }
         }
+        assert!(is_reflected_col(&maps[0], 4));
     }
 }
\ No newline at end of file
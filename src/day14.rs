@@ -14,6 +14,8 @@
 /// We do this by detecting the period of the rock movements and extrapolating
 /// the result.
 /// 
+/// Each move_* function tilts the whole map in a single pass, by sliding every
+/// rock to the cell after the last obstacle (or rock) in its direction.
 /// And yeah, there is duplicated code in the move_* functions, but generalizing
 /// won't really make it more readable, I think, because the order of iterations
 /// is different for each of them.
@@ -21,87 +23,155 @@
 use std::collections::HashSet;
 use crate::utils::*;
 
-type Map = Vec<Vec<char>>;
+pub type Map = Vec<Vec<char>>;
 
 const FLOOR: char = '.';
 const OBSTACLE: char = '#';
 const ROCK: char = 'O';
 
 fn parse(input: &str) -> Vec<Vec<char>> {
-    input.split("\n").map(|line| line.trim().chars().collect()).collect()
+    lines(input).map(|line| line.trim().chars().collect()).collect()
 }
 
+/// Tilts the map up, sliding each rock up to the last obstacle or rock in its
+/// column. Returns true if any rock moved.
 fn move_up(map: &mut Map) -> bool {
     let mut moved = false;
-    for y in (0..map.len()-1) {
-        for x in 0..map[y].len() {
-            if map[y][x] == FLOOR && map[y+1][x] == ROCK {
-                map[y][x] = ROCK;
-                map[y+1][x] = FLOOR;
-                moved = true;
+    for x in 0..map[0].len() {
+        // First free cell that a rock can slide into
+        let mut free = 0;
+        for y in 0..map.len() {
+            match map[y][x] {
+                OBSTACLE => free = y + 1,
+                ROCK => {
+                    if free != y {
+                        map[free][x] = ROCK;
+                        map[y][x] = FLOOR;
+                        moved = true;
+                    }
+                    free += 1;
+                },
+                _ => {},
             }
         }
     }
     moved
 }
 
+/// Tilts the map down, see move_up
 fn move_down(map: &mut Map) -> bool {
     let mut moved = false;
-    for y in (1..map.len()).rev() {
-        for x in 0..map[y].len() {
-            if map[y][x] == FLOOR && map[y-1][x] == ROCK {
-                map[y][x] = ROCK;
-                map[y-1][x] = FLOOR;
-                moved = true;
+    for x in 0..map[0].len() {
+        let mut free = map.len() - 1;
+        for y in (0..map.len()).rev() {
+            match map[y][x] {
+                OBSTACLE => free = y.wrapping_sub(1),
+                ROCK => {
+                    if free != y {
+                        map[free][x] = ROCK;
+                        map[y][x] = FLOOR;
+                        moved = true;
+                    }
+                    free = free.wrapping_sub(1);
+                },
+                _ => {},
             }
         }
     }
     moved
 }
 
-
+/// Tilts the map left, see move_up
 fn move_left(map: &mut Map) -> bool {
     let mut moved = false;
-    for x in (0..map[0].len()-1) {
-        for y in 0..map.len() {
-            if map[y][x] == FLOOR && map[y][x+1] == ROCK {
-                map[y][x] = ROCK;
-                map[y][x+1] = FLOOR;
-                moved = true;
+    for row in map.iter_mut() {
+        let mut free = 0;
+        for x in 0..row.len() {
+            match row[x] {
+                OBSTACLE => free = x + 1,
+                ROCK => {
+                    if free != x {
+                        row[free] = ROCK;
+                        row[x] = FLOOR;
+                        moved = true;
+                    }
+                    free += 1;
+                },
+                _ => {},
             }
         }
     }
     moved
 }
 
-
+/// Tilts the map right, see move_up
 fn move_right(map: &mut Map) -> bool {
     let mut moved = false;
-    for x in (1..map[0].len()).rev() {
-        for y in 0..map.len() {
-            if map[y][x] == FLOOR && map[y][x-1] == ROCK {
-                map[y][x] = ROCK;
-                map[y][x-1] = FLOOR;
-                moved = true;
+    for row in map.iter_mut() {
+        let mut free = row.len() - 1;
+        for x in (0..row.len()).rev() {
+            match row[x] {
+                OBSTACLE => free = x.wrapping_sub(1),
+                ROCK => {
+                    if free != x {
+                        row[free] = ROCK;
+                        row[x] = FLOOR;
+                        moved = true;
+                    }
+                    free = free.wrapping_sub(1);
+                },
+                _ => {},
             }
         }
     }
     moved
 }
 
+/// Compact representation of a map for cycle detection: bitsets of the rock
+/// and obstacle cells, in row-major order
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct BoardKey {
+    rocks: Vec<u64>,
+    obstacles: Vec<u64>,
+}
+
+impl BoardKey {
+    fn of(map: &Map) -> BoardKey {
+        let cell_count = map.iter().map(|row| row.len()).sum::<usize>();
+        let mut key = BoardKey {
+            rocks: vec![0; cell_count.div_ceil(64)],
+            obstacles: vec![0; cell_count.div_ceil(64)],
+        };
+        for (i, &cell) in map.iter().flatten().enumerate() {
+            let bits = match cell {
+                ROCK => &mut key.rocks,
+                OBSTACLE => &mut key.obstacles,
+                _ => continue,
+            };
+            bits[i / 64] |= 1 << (i % 64);
+        }
+        key
+    }
+}
 
-fn weight(map: &Map) -> I {
-    let height = map.len();
+/// Load on the north support beams: Each rock weighs the number of rows from
+/// its row to the south edge (including its own row)
+pub fn weight(map: &Map) -> I {
+    let height = map.len() as I;
     map.iter()
         .enumerate()
-        .map(|(i, row)| (height - i) * row.iter().filter(|&&cell| cell == ROCK).count())
-        .sum::<usize>() as I
+        .map(|(i, row)| {
+            let rows_to_south_edge = height.saturating_sub(i as I);
+            let rocks = row.iter().filter(|&&cell| cell == ROCK).count() as I;
+            rows_to_south_edge.saturating_mul(rocks)
+        })
+        .fold(0, I::saturating_add)
 }
 
 
 pub fn part1(input: &str) -> I {
     let mut map = parse(input);
-    while move_up(&mut map) {}
+    move_up(&mut map);
     weight(&map)
 }
 
@@ -112,28 +182,34 @@ fn printmap(map: &Map) {
     println!();
 }
 
+/// Runs one spin cycle: tilts the map up, left, down and right
+pub fn spin_cycle(map: &mut Map) {
+    move_up(map);
+    move_left(map);
+    move_down(map);
+    move_right(map);
+}
+
 pub fn part2(input: &str) -> I {
     let mut map = parse(input);
 
-    let mut seen_maps = HashMap::<Map, I>::new();
+    let mut seen_maps = HashMap::<BoardKey, I>::new();
 
     let mut i = 0;
     let mut has_skipped  = false;
     while i < 1_000_000_000 {
-        while move_up(&mut map) {}
-        while move_left(&mut map) {}
-        while move_down(&mut map) {}
-        while move_right(&mut map) {}
+        spin_cycle(&mut map);
 
-        if !has_skipped && seen_maps.contains_key(&map) {
-            let period = i - seen_maps[&map];
+        let key = BoardKey::of(&map);
+        if !has_skipped && seen_maps.contains_key(&key) {
+            let period = i - seen_maps[&key];
             let skip = (1_000_000_000 - i) / period;
             println!("Period {}, skipping {} cycles, from {} to {}", period, skip, i, i + skip * period);
             i += skip * period;
             has_skipped = true;
         }
 
-        seen_maps.insert(map.clone(), i);
+        seen_maps.insert(key, i);
 
         if i % 10_000 == 0 {
             println!("{}", i);
@@ -201,5 +277,102 @@ mod tests {
         assert_eq!(map, vec![vec!['O'],vec!['O'],vec!['.'],]);
     }
 
+    #[test]
+    fn test_spin_cycle_loads() {
+        let mut map = parse("\
+        O....#....
+        O.OO#....#
+        .....##...
+        OO.#O....O
+        .O.....O#.
+        O.#..O.#.#
+        ..O..#O..O
+        .......O..
+        #....###..
+        #OO..#....");
 
-}
\ No newline at end of file
+        let loads = (0..10).map(|_| {
+            spin_cycle(&mut map);
+            weight(&map)
+        }).vec();
+        assert_eq!(loads, vec![87, 69, 69, 69, 65, 64, 65, 63, 68, 69]);
+    }
+
+    #[test]
+    fn test_board_key() {
+        let map = parse("\
+        O....#....
+        O.OO#....#
+        .....##...
+        OO.#O....O
+        .O.....O#.
+        O.#..O.#.#
+        ..O..#O..O
+        .......O..
+        #....###..
+        #OO..#....");
+
+        assert_eq!(BoardKey::of(&map), BoardKey::of(&map.clone()));
+
+        let mut moved = map.clone();
+        move_up(&mut moved);
+        assert_ne!(BoardKey::of(&map), BoardKey::of(&moved));
+
+        // Rocks and obstacles are distinguished
+        assert_ne!(BoardKey::of(&parse("O.")), BoardKey::of(&parse("#.")));
+        assert_ne!(BoardKey::of(&parse("O.")), BoardKey::of(&parse(".O")));
+    }
+
+    #[test]
+    fn test_tilt_in_one_pass() {
+        let map = parse("\
+        ..O.
+        #..O
+        .O.#
+        O..O");
+
+        let tilted = |tilt: fn(&mut Map) -> bool| {
+            let mut map = map.clone();
+            assert!(tilt(&mut map));
+            // A second tilt must not move anything
+            assert!(!tilt(&mut map));
+            map
+        };
+
+        assert_eq!(tilted(move_up), parse("\
+        .OOO
+        #...
+        O..#
+        ...O"));
+        assert_eq!(tilted(move_down), parse("\
+        ....
+        #..O
+        ...#
+        OOOO"));
+        assert_eq!(tilted(move_left), parse("\
+        O...
+        #O..
+        O..#
+        OO.."));
+        assert_eq!(tilted(move_right), parse("\
+        ...O
+        #..O
+        ..O#
+        ..OO"));
+    }
+
+    #[test]
+    fn test_weight() {
+        // A single row is one row from the south edge
+        assert_eq!(weight(&parse("O.O#O")), 3);
+        assert_eq!(weight(&parse("..#..")), 0);
+
+        // Tall grid: Only the top and bottom rows contain rocks
+        let height = 10_000;
+        let mut map = parse(&vec![".O."; height].join("\n"));
+        for row in &mut map[1..height - 1] {
+            row.fill(FLOOR);
+        }
+        assert_eq!(weight(&map), height as I + 1);
+    }
+}
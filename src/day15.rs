@@ -17,30 +17,80 @@ use crate::utils::parse::*;
 
 // Lenses are (label, focal length) pairs
 type Lens = (String, I);
-// Box is a list of lenses
-type Boxes = Vec<Vec<Lens>>;
+
+/// A box of lenses, indexed by label. Keeps track of the insertion order of
+/// the labels, so that lookups don't need to scan the list of lenses.
+#[derive(Clone, Default, Debug)]
+struct LensBox {
+    /// Maps labels to (insertion order, focal length)
+    lenses: HashMap<String, (usize, I)>,
+    next_order: usize,
+}
+
+impl LensBox {
+    /// Replaces the focal length of the lens with the same label (keeping its
+    /// position), or adds the lens to the back of the box
+    pub fn insert(&mut self, label: String, focal: I) {
+        if let Some((_, lens_focal)) = self.lenses.get_mut(&label) {
+            *lens_focal = focal;
+        } else {
+            self.lenses.insert(label, (self.next_order, focal));
+            self.next_order += 1;
+        }
+    }
+
+    /// Removes the lens with the label, if there is one
+    pub fn remove(&mut self, label: &str) {
+        self.lenses.remove(label);
+    }
+
+    /// Returns the lenses in the box, from front to back
+    pub fn lenses(&self) -> Vec<Lens> {
+        self.lenses.iter()
+            .sorted_by_key(|(_, (order, _))| order)
+            .map(|(label, &(_, focal))| (label.clone(), focal))
+            .vec()
+    }
+}
+
+type Boxes = Vec<LensBox>;
+
+/// Hash function as defined in the puzzle, on the ASCII bytes of the input
+const fn hash_bytes(bytes: &[u8]) -> u8 {
+    let mut hash = 0u8;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash = hash.wrapping_add(bytes[i]).wrapping_mul(17);
+        i += 1;
+    }
+    hash
+}
 
 /// Hash function for a lens as defined in the puzzle
 fn hash(input: &str) -> I {
-    input.chars().fold(0i64, |acc, c| ((acc + (c as i64)) * 17) % 256)
+    hash_bytes(input.as_bytes()) as I
 }
 
-fn apply_step(mut boxes: Boxes, step: &str) -> Boxes {
-    // Parse the step
+/// A map operation: Insert (or replace) a lens, or remove the lens with the label
+#[derive(Clone, Eq, PartialEq, Debug)]
+enum Step {
+    Insert { label: String, focal: I },
+    Remove { label: String },
+}
+
+/// Parses a step, like "rn=1" or "cm-"
+fn parse_step(step: &str) -> Step {
     let (label, op, focal_len) = (id, one_of(['=', '-']), opt(int)).parse(step).unwrap();
-    let lensbox = &mut boxes[hash(&label) as usize];
+    match op {
+        '=' => Step::Insert { label, focal: focal_len.unwrap() },
+        _ => Step::Remove { label },
+    }
+}
 
-    // Does the box contain a lens with the same label? Then replace or remove it
-    // depending on the operation
-    if let Some(index) = lensbox.iter().position(|(l, _)| l == &label) {
-        if op == '=' {
-            lensbox[index] = (label, focal_len.unwrap());
-        } else {
-            lensbox.remove(index);
-        }
-    } else if op == '=' {
-        // No lens with this label yet, and op is '='? Then add a new lens
-        lensbox.push((label, focal_len.unwrap()));
+fn apply_step(mut boxes: Boxes, step: Step) -> Boxes {
+    match step {
+        Step::Insert { label, focal } => boxes[hash(&label) as usize].insert(label, focal),
+        Step::Remove { label } => boxes[hash(&label) as usize].remove(&label),
     }
     boxes
 }
@@ -49,7 +99,7 @@ fn focal_power(boxes: &Boxes) -> I {
     // Focal power uses box index, lens index and focal length.
     // Iterate all lenses and return the sum of the focal powers
     boxes.iter().enumerate().map(|(box_index, lensbox)| {
-        lensbox.iter().enumerate().map(move |(lens_index, (_, focal_len))| {
+        lensbox.lenses().into_iter().enumerate().map(move |(lens_index, (_, focal_len))| {
             (1+box_index as I) * (1+lens_index as I) * focal_len
         })
     }).flatten().sum()
@@ -57,14 +107,20 @@ fn focal_power(boxes: &Boxes) -> I {
 
 /// Part 1: Sum of all hashes
 pub fn part1(input: &str) -> I {
-    input.split(",").map(hash).sum()
+    part1_bytes(input.as_bytes())
+}
+
+/// Part 1 on the raw input bytes, for inputs that have not been validated as
+/// UTF-8 (the puzzle input is plain ASCII)
+pub fn part1_bytes(input: &[u8]) -> I {
+    input.split(|&b| b == b',').map(|step| hash_bytes(step) as I).sum()
 }
 
 /// Part 2: Perform all the operations and calculate the focal power
 pub fn part2(input: &str) -> I {
-    let steps = input.split(",");
+    let steps = input.split(",").map(parse_step);
     // Start with 256 empty boxes and execute the steps on them
-    let boxes = steps.fold(vec![vec![]; 256], apply_step);
+    let boxes = steps.fold(vec![LensBox::default(); 256], apply_step);
     focal_power(&boxes)
 }
 
@@ -78,4 +134,49 @@ mod tests {
         assert_eq!(part1(example_input), 1320);
         assert_eq!(part2(example_input), 145);
     }
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(hash("HASH"), 52);
+        for label in ["rn", "cm", "qp", "pc", "ot", "ab", "rn=1", "qp-", ""] {
+            let expected = label.chars().fold(0i64, |acc, c| ((acc + (c as i64)) * 17) % 256);
+            assert_eq!(hash_bytes(label.as_bytes()) as I, expected);
+            assert_eq!(hash(label), expected);
+        }
+    }
+
+    #[test]
+    fn test_part1_bytes() {
+        let example_input = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
+        assert_eq!(part1_bytes(example_input.as_bytes()), 1320);
+        for input in [example_input, "HASH", "", "a,,b"] {
+            assert_eq!(part1_bytes(input.as_bytes()), input.split(',').map(hash).sum::<I>());
+        }
+    }
+
+    #[test]
+    fn test_lens_box_order() {
+        let mut lensbox = LensBox::default();
+        lensbox.insert("a".to_owned(), 1);
+        lensbox.insert("b".to_owned(), 2);
+        lensbox.insert("c".to_owned(), 3);
+        // Replacing keeps the position
+        lensbox.insert("a".to_owned(), 4);
+        assert_eq!(lensbox.lenses(), vec![("a".to_owned(), 4), ("b".to_owned(), 2), ("c".to_owned(), 3)]);
+
+        // Removing and inserting again moves the lens to the back
+        lensbox.remove("a");
+        lensbox.remove("d");
+        lensbox.insert("a".to_owned(), 5);
+        lensbox.remove("b");
+        lensbox.insert("b".to_owned(), 6);
+        lensbox.remove("b");
+        assert_eq!(lensbox.lenses(), vec![("c".to_owned(), 3), ("a".to_owned(), 5)]);
+    }
+
+    #[test]
+    fn test_parse_step() {
+        assert_eq!(parse_step("rn=1"), Step::Insert { label: "rn".to_owned(), focal: 1 });
+        assert_eq!(parse_step("cm-"), Step::Remove { label: "cm".to_owned() });
+    }
 }
\ No newline at end of file
@@ -17,9 +17,36 @@ use crate::{utils::*, vec2::Vec2};
 
 type Map = Vec<Vec<char>>;
 
-/// Returns the input map as a 2D vector of chars
+/// Returns the input map as a 2D vector of chars. Rows that are shorter than
+/// the longest row are padded with empty space.
 fn parse(input: &str) -> Map {
-    input.split("\n").map(|line| line.trim().chars().vec()).vec()
+    let mut map = lines(input).map(|line| line.trim().chars().vec()).vec();
+    let width = Bounds::of(&map).w;
+    for row in map.iter_mut() {
+        row.resize(width, '.');
+    }
+    map
+}
+
+/// Size of the map
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+struct Bounds {
+    w: usize,
+    h: usize,
+}
+
+impl Bounds {
+    /// Bounds of the map, using the longest row as width
+    fn of(map: &Map) -> Bounds {
+        Bounds {
+            w: map.iter().map(|row| row.len()).max().unwrap_or(0),
+            h: map.len(),
+        }
+    }
+
+    fn contains(&self, pos: Vec2) -> bool {
+        pos.0 >= 0 && pos.1 >= 0 && (pos.0 as usize) < self.w && (pos.1 as usize) < self.h
+    }
 }
 
 /// (beam position, beam direction)
@@ -29,68 +56,86 @@ type Beam = (Vec2, Vec2);
 /// When the beam hits a splitter, the result will contain two beams.
 /// When the beam leaves the map, the result will be empty.
 /// Otherwise the result will contain a single beam.
-fn beam_step(beam: Beam, map: &Map) -> Vec<Beam> {
+fn beam_step(beam: Beam, map: &Map, bounds: &Bounds) -> Vec<Beam> {
     let (pos, dir) = beam;
     // Advance the beam into its direction
     let new_pos = pos + dir;
-    let (x, y) = (new_pos.0 as usize, new_pos.1 as usize);
 
     // Check if the beam is still on the map
-    if new_pos.0 < 0 || new_pos.1 < 0 || x >= map[0].len() || y >= map.len() {
+    if !bounds.contains(new_pos) {
         // Beam is off the map: no output beam.
-        return vec![];
+        vec![]
     } else {
-        // Beam is still on the map. The output beams depend on what is on the map.
-        match map[y][x] {
-            // Empty space: nothing happens, beam continues
-            '.' => vec![(new_pos, dir)],
-
-            // Mirrors: rotate beam direction by 90 degrees.
-            '/' => vec![(new_pos, Vec2(-dir.1, -dir.0))],
-            '\\' => vec![(new_pos, Vec2(dir.1, dir.0))],
-
-            // Splitters: Output two beams, rotated by +90 and -90 degrees.
-            // (unless the beam passes in the direction of the splitter axis)
-            '|' => if dir.0 == 0 {
-                        vec![(new_pos, dir)]
-                    } else {
-                        vec![
-                            (new_pos, Vec2(0, 1)),
-                            (new_pos, Vec2(0, -1))
-                        ]
-                    },
-            '-' => if dir.1 == 0 {
-                        vec![(new_pos, dir)]
-                    } else {
-                        vec![
-                            (new_pos, Vec2(1, 0)),
-                            (new_pos, Vec2(-1, 0))
-                        ]
-                    },
-            _ => unreachable!()
-        }
+        enter_cell(map, new_pos, dir)
     }
 }
 
-/// Simulates the full beam path starting at the given beam.
-fn simulate_beam(map: &Map, start: Beam) -> I {
-    // Initially, we have one beam, but during the simulation it will be split
-    // into multiple beams.
-    let mut beams = vec![(start.0 - start.1, start.1)];
+/// Returns the beams leaving the given cell, when a beam with the given
+/// direction enters it
+fn enter_cell(map: &Map, pos: Vec2, dir: Vec2) -> Vec<Beam> {
+    let (x, y) = (pos.0 as usize, pos.1 as usize);
+
+    // The output beams depend on what is on the map.
+    match map[y][x] {
+        // Empty space: nothing happens, beam continues
+        '.' => vec![(pos, dir)],
+
+        // Mirrors: rotate beam direction by 90 degrees.
+        '/' => vec![(pos, Vec2(-dir.1, -dir.0))],
+        '\\' => vec![(pos, Vec2(dir.1, dir.0))],
+
+        // Splitters: Output two beams, rotated by +90 and -90 degrees.
+        // (unless the beam passes in the direction of the splitter axis)
+        '|' => if dir.0 == 0 {
+                    vec![(pos, dir)]
+                } else {
+                    vec![
+                        (pos, Vec2(0, 1)),
+                        (pos, Vec2(0, -1))
+                    ]
+                },
+        '-' => if dir.1 == 0 {
+                    vec![(pos, dir)]
+                } else {
+                    vec![
+                        (pos, Vec2(1, 0)),
+                        (pos, Vec2(-1, 0))
+                    ]
+                },
+        _ => unreachable!()
+    }
+}
+
+/// Simulates the full beam path of a beam at the given position and direction.
+/// If light_start_cell is true, the beam enters the start cell (so it is lit,
+/// and its mirror or splitter applies). Otherwise, the beam only starts moving
+/// from there, and the start cell is only lit if a beam comes back to it.
+fn simulate_beam_from(map: &Map, pos: Vec2, dir: Vec2, light_start_cell: bool) -> I {
+    let bounds = Bounds::of(map);
+    if !bounds.contains(pos) {
+        return 0;
+    }
+
+    // Initially, we have one beam (or two, if the start cell is a splitter),
+    // but during the simulation it will be split into multiple beams.
+    let mut beams = if light_start_cell { enter_cell(map, pos, dir) } else { vec![(pos, dir)] };
 
     // Keep track of the cells that are lit by the beam
     let mut lit_cells = HashSet::<Vec2>::new();
+    if light_start_cell {
+        lit_cells.insert(pos);
+    }
 
     // Beams will go in cycles, so we keep track of all beam position/direction
     // pairs that we have seen before. We ignore duplicate beams, so that we
     // can terminate the simulation at some point.
-    let mut seen_beams = HashSet::<(Vec2, Vec2)>::new();
+    let mut seen_beams = HashSet::<(Vec2, Vec2)>::from_iter(beams.iter().copied());
 
     // Keep simulating until we don't see any new beams anymore
     while beams.len() > 0 {
         // Simulate all beams and filter out the ones that we have seen before
         beams = beams.iter()
-            .map(|beam| beam_step(*beam, &map))
+            .map(|beam| beam_step(*beam, map, &bounds))
             .flatten()
             .filter(|beam| seen_beams.insert(*beam))
             .vec();
@@ -107,29 +152,30 @@ fn simulate_beam(map: &Map, start: Beam) -> I {
 /// return the number of lit cells.
 pub fn part1(input: &str) -> I {
     let map = parse(input);
-    simulate_beam(&map, (Vec2(0,0), Vec2(1,0)))
+    simulate_beam_from(&map, Vec2(0,0), Vec2(1,0), true)
 }
 
 /// Part 2: Find the beam starting position that lights the most cells,
 /// return that maximum number of lit cells.
 pub fn part2(input: &str) -> I {
     let map = parse(input);
+    let Bounds { w, h } = Bounds::of(&map);
 
-    // All possible starting positions
+    // All possible starting positions (none for an empty map)
     let possible_starts = [
-        (0..map[0].len()).map(|x| (Vec2(x as I, 0), Vec2(0, 1))).vec(),
-        (0..map[0].len()).map(|x| (Vec2(x as I, map.len() as I - 1), Vec2(0, -1))).vec(),
-        (0..map.len()).map(|y| (Vec2(0, y as I), Vec2(1, 0))).vec(),
-        (0..map.len()).map(|y| (Vec2(map[0].len() as I - 1, y as I), Vec2(-1, 0))).vec(),
+        (0..w).map(|x| (Vec2(x as I, 0), Vec2(0, 1))).vec(),
+        (0..w).map(|x| (Vec2(x as I, h as I - 1), Vec2(0, -1))).vec(),
+        (0..h).map(|y| (Vec2(0, y as I), Vec2(1, 0))).vec(),
+        (0..h).map(|y| (Vec2(w as I - 1, y as I), Vec2(-1, 0))).vec(),
     ];
     
     // Simulate the beam for all possible starting positions and return the
     // maximum number of lit cells.
     possible_starts.iter()
         .flatten()
-        .map(|beam| simulate_beam(&map, *beam))
+        .map(|&(pos, dir)| simulate_beam_from(&map, pos, dir, true))
         .max()
-        .unwrap()
+        .unwrap_or(0)
 }
 
 #[cfg(test)]
@@ -151,4 +197,32 @@ mod tests {
         assert_eq!(part1(input), 46);
         assert_eq!(part2(input), 51);
     }
+
+    #[test]
+    fn test_light_start_cell() {
+        let map = parse(r"\..
+                          ...
+                          ...");
+        // Entering the mirror sends the beam down
+        assert_eq!(simulate_beam_from(&map, Vec2(0,0), Vec2(1,0), true), 3);
+        // Starting on the mirror ignores it, and doesn't light it
+        assert_eq!(simulate_beam_from(&map, Vec2(0,0), Vec2(1,0), false), 2);
+    }
+
+    #[test]
+    fn test_empty_map() {
+        assert_eq!(part1(""), 0);
+        assert_eq!(part2(""), 0);
+    }
+
+    #[test]
+    fn test_ragged_map() {
+        // Missing cells at the end of short rows are empty space
+        let input = r"..\
+                      .
+                      ..|.";
+        assert_eq!(Bounds::of(&parse(input)), Bounds { w: 4, h: 3 });
+        assert_eq!(part1(input), 3 + 2);
+        assert_eq!(part1(input), part1("..\\.\n....\n..|."));
+    }
 }
\ No newline at end of file
@@ -17,26 +17,198 @@
 /// The solution uses Dijkstra to find the cheapest path. Adding the usual
 /// optimistic distance heuristic does not help much because of the
 /// same-direction restrictions.
+/// The search still runs as A* with an admissible heuristic (see
+/// min_cost_heuristic), which might help for other inputs.
+/// The search itself only knows the DijkstraState trait, so it can be reused
+/// for other kinds of search states.
 
+use std::{cmp::Reverse, collections::BinaryHeap};
 use crate::{utils::*, vec2::Vec2};
 
-const NEIGHBOR_DIRECTIONS: [Vec2; 4] = [
-    Vec2(0, -1),
-    Vec2(0, 1),
-    Vec2(-1, 0),
-    Vec2(1, 0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub fn offset(&self) -> Vec2 {
+        match self {
+            Direction::Up => Vec2(0, -1),
+            Direction::Down => Vec2(0, 1),
+            Direction::Left => Vec2(-1, 0),
+            Direction::Right => Vec2(1, 0),
+        }
+    }
+
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+}
+
+const NEIGHBOR_DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
 ];
 
 type Map = Vec<Vec<I>>;
 
+/// A state of a search with Dijkstra (or A*), for grids and other graphs with
+/// non-negative costs. The Context holds the data that is the same for all
+/// states, like the map.
+trait DijkstraState<Context>: Sized {
+    /// Identifies a search state for the visited set. States with the same key
+    /// have the same options for continuing, so we only need to explore one of them.
+    type Key: Eq + std::hash::Hash;
+
+    fn key(&self) -> Self::Key;
+    /// Total cost of getting to this state
+    fn cost(&self) -> I;
+    fn is_goal(&self, context: &Context) -> bool;
+    /// The states that can be reached from this state in a single step
+    fn successors(&self, context: &Context) -> Vec<Self>;
+}
+
+/// Finds the cheapest path from the start state to a goal state, using A* with
+/// the given heuristic (or Dijkstra, if the heuristic is always zero).
+/// The heuristic is only used for choosing the next state to explore, the cost
+/// of the states does not include it. It must never overestimate the remaining
+/// cost (and not drop by more than the cost of a step), otherwise the path
+/// might not be the cheapest.
+/// Returns the states of the path, and the number of explored states, or None
+/// if no goal can be reached.
+fn dijkstra<C, S: DijkstraState<C>>(context: &C, start: S, heuristic: impl Fn(&S) -> I) -> Option<(Vec<S>, usize)> {
+    // All states that were added to the open list, with the index of their previous state in this list. Kept for reconstructing the path
+    let mut states = vec![(start, None)];
+    // Open states, i.e. states to be explored, as (cost plus estimated remaining cost, index in states), cheapest first
+    let mut open = BinaryHeap::from([Reverse((states[0].0.cost() + heuristic(&states[0].0), 0))]);
+    // Keys of the explored states. A state is only final once it is explored,
+    // before that, it might still be reached more cheaply.
+    let mut visited = HashSet::<S::Key>::new();
+
+    // Keep exploring the open states until we reach the goal
+    while let Some(Reverse((_, index))) = open.pop() {
+        let state = &states[index].0;
+
+        // Skip states that were already explored via a cheaper path
+        if !visited.insert(state.key()) {
+            continue;
+        }
+
+        // If we reach the goal, reconstruct the path and return it
+        if state.is_goal(context) {
+            return Some((reconstruct_path(states, index), visited.len()));
+        }
+
+        for next_state in state.successors(context) {
+            if !visited.contains(&next_state.key()) {
+                open.push(Reverse((next_state.cost() + heuristic(&next_state), states.len())));
+                states.push((next_state, Some(index)));
+            }
+        }
+    }
+
+    None
+}
+
+/// Reconstructs the path to the state at the given index from the list of states
+fn reconstruct_path<S>(states: Vec<(S, Option<usize>)>, index: usize) -> Vec<S> {
+    let mut indices = vec![index];
+    while let Some(previous) = states[*indices.last().unwrap()].1 {
+        indices.push(previous);
+    }
+
+    let mut states = states.into_iter().map(|(state, _)| Some(state)).vec();
+    indices.iter().rev().map(|&i| states[i].take().unwrap()).vec()
+}
+
+/// The map, and the minimum and maximum steps that the crucible can go in the same direction
+struct Crucible<'a> {
+    map: &'a Map,
+    min_straight: I,
+    max_straight: I,
+}
+
+impl Crucible<'_> {
+    fn goal(&self) -> Vec2 {
+        Vec2(self.map[0].len() as I - 1, self.map.len() as I - 1)
+    }
+
+    fn is_in_map(&self, Vec2(x, y): Vec2) -> bool {
+        x >= 0 && y >= 0 && x < self.map[0].len() as I && y < self.map.len() as I
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 struct Node {
     pub pos: Vec2,
-    pub previous_index: Option<usize>,
+    /// Direction in which we entered this node (None for the start node)
+    pub direction: Option<Direction>,
     pub same_dir_count: I,
     pub loss: I,
 }
 
+/// Identifies a search state for the visited set. Nodes with the same key have
+/// the same options for continuing, so we only need to explore one of them.
+type StateKey = (Vec2, Option<Direction>, I);
+
+impl Node {
+    pub fn state_key(&self) -> StateKey {
+        (self.pos, self.direction, self.same_dir_count)
+    }
+}
+
+impl DijkstraState<Crucible<'_>> for Node {
+    type Key = StateKey;
+
+    fn key(&self) -> StateKey {
+        self.state_key()
+    }
+
+    fn cost(&self) -> I {
+        self.loss
+    }
+
+    fn is_goal(&self, crucible: &Crucible) -> bool {
+        self.pos == crucible.goal() && self.same_dir_count >= crucible.min_straight-1
+    }
+
+    fn successors(&self, crucible: &Crucible) -> Vec<Node> {
+        let previous_dir = self.direction;
+
+        // Find all possible next nodes
+        NEIGHBOR_DIRECTIONS.iter()
+            // Only produce neighbor nodes for inside the map
+            .filter(|direction| crucible.is_in_map(direction.offset() + self.pos))
+            .map(|&direction| Node {
+                pos: self.pos + direction.offset(),
+                direction: Some(direction),
+                same_dir_count: if Some(direction) == previous_dir || previous_dir.is_none() { self.same_dir_count + 1 } else { 1 },
+                loss: self.loss + crucible.map[self.pos.1 as usize][self.pos.0 as usize]
+            })
+            // Consider this neighbor if:
+            // - we are not going back to our old position,
+            // - we are either going straight or we can turn already (same_dir_count >= min_straight)
+            // - we are not going straight too far.
+            .filter(|next_node| {
+                let direction = next_node.direction.unwrap();
+                Some(direction.opposite()) != previous_dir
+                    && (self.same_dir_count >= crucible.min_straight || Some(direction) == previous_dir || previous_dir.is_none())
+                    && next_node.same_dir_count <= crucible.max_straight
+            })
+            .vec()
+    }
+}
+
 /// Parses the input grid into a vector of ints
 fn parse_input(input: &str) -> Vec<Vec<I>> {
     input.lines().map(|line| {
@@ -46,78 +218,26 @@ fn parse_input(input: &str) -> Vec<Vec<I>> {
     }).vec()
 }
 
-/// Reconstructs the path from the closed list
-fn reconstruct_path(node: &Node, closed: &Vec<Node>) -> Vec<Vec2> {
-    let mut path = vec![node.pos];
-    let mut cursor = node.clone();
-
-    while let Some(index) = cursor.previous_index {
-        cursor = closed[index];
-        path.insert(0, cursor.pos);
-    }
-
-    path
-}
-
-/// Path finding, using Dijkstra
+/// Path finding, using A* with min_cost_heuristic
 fn find_path(map: &Map, min_straight: I, max_straight: I) -> Vec<Vec2> {
-    let width = map[0].len() as I;
-    let height = map.len() as I;
-    let goal = Vec2(width - 1, height - 1);
-
-    let is_in_map = |Vec2(x, y): Vec2| x >= 0 && y >= 0 && x < width && y < height;
-    
-    // List of open nodes, i.e. nodes to be explored
-    let mut open = vec![Node::default()];
-    // List of closed nodes, i.e. nodes already explored. Kept for reconstructing the path
-    let mut closed = Vec::<Node>::new();
-    // List of visited nodes, for fast skipping of already visited nodes during exploration
-    let mut visited = HashSet::<(Vec2, Option<Vec2>, I)>::new();
-
-    // Keep exploring the open nodes until we reach the goal
-    loop {
-        // Get the node with the lowest heat loss
-        let (node_index, &node) = open.iter().enumerate().min_by_key(|(_,node)| node.loss).unwrap();
-        open.remove(node_index);
-        // Add the node to the closed list (for reconstructing the path later)
-        closed.push(node.clone());
-        let node_index_in_closed = closed.len() - 1;
+    find_path_with_heuristic(map, min_straight, max_straight, min_cost_heuristic(map)).0
+}
 
-        // If we reach the goal, reconstruct the path and return it
-        if node.pos == goal && node.same_dir_count >= min_straight-1 {
-            return reconstruct_path(&node, &closed);
-        }
+/// Admissible A* heuristic: The remaining Manhattan distance to the goal times
+/// the minimum cell cost of the map
+fn min_cost_heuristic(map: &Map) -> impl Fn(Vec2) -> I {
+    let goal = Vec2(map[0].len() as I - 1, map.len() as I - 1);
+    let min_cost = map.iter().flatten().copied().min().unwrap();
+    move |pos| (goal - pos).manhattan() * min_cost
+}
 
-        let previous_pos = node.previous_index.map(|prev_index| closed[prev_index].pos);
-        let previous_dir = previous_pos.map(|previous_pos| node.pos - previous_pos);
-    
-        // Find all possible next nodes
-        for &direction in &NEIGHBOR_DIRECTIONS {
-            // Only produce neighbor nodes for inside the map
-            if is_in_map(direction + node.pos) {
-                let next_node = Node {
-                    pos: node.pos + direction,
-                    previous_index: Some(node_index_in_closed),
-                    same_dir_count: if Some(direction) == previous_dir || previous_dir.is_none() { node.same_dir_count + 1 } else { 1 },
-                    loss: node.loss + map[node.pos.1 as usize][node.pos.0 as usize]
-                };
-
-                // Consider this neighbor if:
-                // - we are not going back to our old position, 
-                // - we are either going straight or we can turn already (same_dir_count >= min_straight)
-                // - we are not going straight too far.
-                // - we have not visited this node before (we insert it while checking)
-                if Some(next_node.pos) != previous_pos
-                    && (node.same_dir_count >= min_straight || Some(direction) == previous_dir || previous_dir.is_none())
-                    && next_node.same_dir_count <= max_straight
-                    && visited.insert((next_node.pos, Some(node.pos), next_node.same_dir_count)) 
-                {
-                    // Everything ok? Then add the node to the open list
-                    open.push(next_node);
-                }
-            }
-        }
-    }
+/// Path finding for the crucible, using A* with the given heuristic (or Dijkstra,
+/// if the heuristic is always zero).
+/// Returns the path, and the number of explored nodes.
+fn find_path_with_heuristic(map: &Map, min_straight: I, max_straight: I, heuristic: impl Fn(Vec2) -> I) -> (Vec<Vec2>, usize) {
+    let crucible = Crucible { map, min_straight, max_straight };
+    let (path, explored) = dijkstra(&crucible, Node::default(), |node| heuristic(node.pos)).expect("No path found");
+    (path.iter().map(|node| node.pos).vec(), explored)
 }
 
 /// Finds the minimum heat loss for getting from the top left corner to the bottom right corner,
@@ -172,4 +292,116 @@ mod tests {
         assert_eq!(part1(input), 102);
         assert_eq!(part2(input), 94);
     }
+
+    #[test]
+    fn test_heuristic() {
+        let input = "\
+            2413432311323
+            3215453535623
+            3255245654254
+            3446585845452
+            4546657867536
+            1438598798454
+            4457876987766
+            3637877979653
+            4654967986887
+            4564679986453
+            1224686865563
+            2546548887735
+            4322674655533";
+
+        let map = parse_input(input);
+        let heat_loss = |path: &Vec<Vec2>| path.iter().skip(1).map(|p| map[p.1 as usize][p.0 as usize]).sum::<I>();
+
+        for (min_straight, max_straight, expected) in [(0, 3, 102), (4, 10, 94)] {
+            let (dijkstra_path, dijkstra_explored) = find_path_with_heuristic(&map, min_straight, max_straight, |_| 0);
+            let (a_star_path, a_star_explored) = find_path_with_heuristic(&map, min_straight, max_straight, min_cost_heuristic(&map));
+
+            assert_eq!(heat_loss(&dijkstra_path), expected);
+            assert_eq!(heat_loss(&a_star_path), expected);
+            assert!(a_star_explored < dijkstra_explored, "{} >= {}", a_star_explored, dijkstra_explored);
+        }
+    }
+
+    #[test]
+    fn test_state_key_direction() {
+        let from_left = Node { pos: Vec2(2, 2), direction: Some(Direction::Right), same_dir_count: 2, ..Default::default() };
+        let from_above = Node { direction: Some(Direction::Down), ..from_left };
+
+        // Same position and step count, but different incoming directions are different states
+        assert_ne!(from_left.state_key(), from_above.state_key());
+        let visited = HashSet::from([from_left.state_key(), from_above.state_key()]);
+        assert_eq!(visited.len(), 2);
+
+        // The loss doesn't matter
+        let other_path = Node { loss: 17, ..from_left };
+        assert_eq!(from_left.state_key(), other_path.state_key());
+    }
+
+    /// Unconstrained grid path: Each step into a cell costs the value of the cell
+    #[derive(Clone, Copy)]
+    struct GridState { pos: Vec2, cost: I }
+
+    impl DijkstraState<Map> for GridState {
+        type Key = Vec2;
+
+        fn key(&self) -> Vec2 { self.pos }
+        fn cost(&self) -> I { self.cost }
+        fn is_goal(&self, map: &Map) -> bool {
+            self.pos == Vec2(map[0].len() as I - 1, map.len() as I - 1)
+        }
+        fn successors(&self, map: &Map) -> Vec<GridState> {
+            NEIGHBOR_DIRECTIONS.iter()
+                .map(|direction| self.pos + direction.offset())
+                .filter(|&Vec2(x, y)| x >= 0 && y >= 0 && x < map[0].len() as I && y < map.len() as I)
+                .map(|pos| GridState { pos, cost: self.cost + map[pos.1 as usize][pos.0 as usize] })
+                .vec()
+        }
+    }
+
+    #[test]
+    fn test_unconstrained_grid() {
+        let map = parse_input("\
+            131
+            151
+            421");
+
+        // Cheapest: right, right, down, down (3 + 1 + 1 + 1)
+        let (path, _) = dijkstra(&map, GridState { pos: Vec2(0, 0), cost: 0 }, |_| 0).unwrap();
+        assert_eq!(path.last().unwrap().cost, 6);
+        assert_eq!(path.iter().map(|state| state.pos).vec(), vec![Vec2(0, 0), Vec2(1, 0), Vec2(2, 0), Vec2(2, 1), Vec2(2, 2)]);
+
+        // Without a reachable goal, there is no path
+        let blocked = vec![vec![1]];
+        assert!(dijkstra(&blocked, GridState { pos: Vec2(5, 5), cost: 0 }, |_| 0).is_none());
+    }
+
+    /// Path in a graph with costs on the edges, given as (from, to, cost)
+    #[derive(Clone, Copy)]
+    struct EdgeState { node: char, cost: I }
+
+    impl DijkstraState<Vec<(char, char, I)>> for EdgeState {
+        type Key = char;
+
+        fn key(&self) -> char { self.node }
+        fn cost(&self) -> I { self.cost }
+        fn is_goal(&self, _: &Vec<(char, char, I)>) -> bool { self.node == 'B' }
+        fn successors(&self, edges: &Vec<(char, char, I)>) -> Vec<EdgeState> {
+            edges.iter()
+                .filter(|&&(from, _, _)| from == self.node)
+                .map(|&(_, to, cost)| EdgeState { node: to, cost: self.cost + cost })
+                .vec()
+        }
+    }
+
+    #[test]
+    fn test_edge_costs() {
+        // B is found first via the expensive edge, but the path via A is cheaper
+        let edges = vec![('S', 'A', 1), ('S', 'B', 5), ('A', 'B', 1), ('A', 'S', 1)];
+        let (path, explored) = dijkstra(&edges, EdgeState { node: 'S', cost: 0 }, |_| 0).unwrap();
+        assert_eq!(path.iter().map(|state| state.node).collect::<String>(), "SAB");
+        assert_eq!(path.last().unwrap().cost, 2);
+        // The start is not explored again when going back from A
+        assert_eq!(explored, 3);
+    }
 }
\ No newline at end of file
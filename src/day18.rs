@@ -14,17 +14,17 @@
 /// identical rows. I take advantage of the fact that all edges are either
 /// horizontal or vertical:
 /// 
-///     - I calculate the "area" of a single row by going over all edges
-///       from left to right.
-///     - Whenever I cross a horizontal line, we change between the inside and
-///       outside of the polygon.
-///     - Handling horizontal lines and corners of the polygon is more tricky,
-///       see the code for details.
-///     - Most of the rows contain only vertical lines (because there is only
-///       a very limited number of lines in total). Those typically form
-///       large groups of identical rows, so I can calculate the area for one
-///       of the rows and then multiply it by the number of identical rows.
-///     - I then sum the number of all row areas to get the total polygon area.
+///    - I calculate the "area" of a single row by going over all edges
+///      from left to right.
+///    - Whenever I cross a horizontal line, we change between the inside and
+///      outside of the polygon.
+///    - Handling horizontal lines and corners of the polygon is more tricky,
+///      see the code for details.
+///    - Most of the rows contain only vertical lines (because there is only
+///      a very limited number of lines in total). Those typically form
+///      large groups of identical rows, so I can calculate the area for one
+///      of the rows and then multiply it by the number of identical rows.
+///    - I then sum the number of all row areas to get the total polygon area.
 
 use crate::{utils::*, vec2::Vec2};
 
@@ -62,7 +62,7 @@ fn parse_part2(input: &str) -> Vec<Step> {
         let items = parse::alphanums(line);
         let code = items[2].clone();
         // hex string to int
-        let distance = I::from_str_radix(&code[..5], 16).unwrap();
+        let distance = parse::hex(&code[..5]).expect("Invalid distance code");
         let (direction, orientation) = match code[5..].chars().next().unwrap() {
             '0' => (Vec2(1, 0), Orientation::Horizontal),
             '1' => (Vec2(0, 1), Orientation::Vertical),
@@ -78,9 +78,31 @@ fn parse_part2(input: &str) -> Vec<Step> {
     }).vec()
 }
 
+/// Merges consecutive steps in the same direction into a single step, so that
+/// each straight edge of the polygon is only drawn once. The plan forms a loop,
+/// so the last step is merged into the first step if they have the same direction
+/// (which moves the polygon by the length of the last step, keeping its area).
+fn merge_collinear_steps(plan: Vec<Step>) -> Vec<Step> {
+    let mut merged: Vec<Step> = vec![];
+    for (direction, distance, orientation) in plan {
+        match merged.last_mut() {
+            Some((last_direction, last_distance, _)) if *last_direction == direction => *last_distance += distance,
+            _ => merged.push((direction, distance, orientation)),
+        }
+    }
+
+    if merged.len() > 1 && merged[0].0 == merged[merged.len() - 1].0 {
+        let (_, distance, _) = merged.pop().unwrap();
+        merged[0].1 += distance;
+    }
+    merged
+}
+
 /// Builds the polygon by applying all drawing steps.
 /// Returns a list of (start, end) coordinate pairs of polygon edges.
+/// Collinear consecutive steps form a single edge.
 fn build_edges(plan: Vec<Step>) -> SparseMap {
+    let plan = merge_collinear_steps(plan);
     let mut pos = Vec2(0, 0);
     let mut map = vec![];
     for (direction, distance, orientation) in plan {
@@ -212,6 +234,39 @@ pub fn part2(input: &str) -> I {
 mod tests {
     use super::*;
 
+    /// Reference for the area sweep: Draws the polygon edges into a set of cells,
+    /// flood-fills the outside within the (padded) bounding box, and counts the
+    /// remaining cells. Only feasible for part 1 sized inputs.
+    fn brute_area(plan: Vec<Step>) -> I {
+        let mut edge_cells = HashSet::<Vec2>::new();
+        let mut pos = Vec2(0, 0);
+        edge_cells.insert(pos);
+        for (direction, distance, _) in plan {
+            for _ in 0..distance {
+                pos += direction;
+                edge_cells.insert(pos);
+            }
+        }
+
+        let (min, max) = bounds(edge_cells.iter().copied());
+        let (min, max) = (min - Vec2(1, 1), max + Vec2(1, 1));
+        let is_in_box = |p: Vec2| p.0 >= min.0 && p.0 <= max.0 && p.1 >= min.1 && p.1 <= max.1;
+
+        let mut outside = HashSet::from([min]);
+        let mut queue = vec![min];
+        while let Some(p) = queue.pop() {
+            for dir in [Vec2(1, 0), Vec2(-1, 0), Vec2(0, 1), Vec2(0, -1)] {
+                let next = p + dir;
+                if is_in_box(next) && !edge_cells.contains(&next) && outside.insert(next) {
+                    queue.push(next);
+                }
+            }
+        }
+
+        let box_area = (max.0 - min.0 + 1) * (max.1 - min.1 + 1);
+        box_area - outside.len() as I
+    }
+
     #[test]
     fn test() {
         let input = "\
@@ -230,6 +285,7 @@ mod tests {
         L 2 (#015232)
         U 2 (#7a21e3)";
         assert_eq!(part1(input), 62);
+        assert_eq!(brute_area(parse_part1(input)), 62);
         assert_eq!(part2(input), 952408144115);
     }
 
@@ -241,6 +297,7 @@ mod tests {
         L 1 (#000000)
         U 1 (#000000)";
         assert_eq!(part1(input), 4);
+        assert_eq!(brute_area(parse_part1(input)), 4);
     }
 
     #[test]
@@ -256,6 +313,7 @@ mod tests {
         L 2 (#000000)
         U 2 (#000000)";
         assert_eq!(part1(input), 9);
+        assert_eq!(brute_area(parse_part1(input)), 9);
     }
 
     #[test] fn test_parse_part2() {
@@ -271,4 +329,39 @@ mod tests {
     //     */
     //     assert_eq!(part1(input), 9);
     // }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_collinear_steps() {
+        // Same as test_box2, but the top edge is drawn in two steps
+        let input = "\
+        R 1 (#000000)
+        R 1 (#000000)
+        D 2 (#000000)
+        L 2 (#000000)
+        U 2 (#000000)";
+        assert_eq!(part1(input), 9);
+        assert_eq!(build_edges(parse_part1(input)).len(), 4);
+
+        // Collinear steps at the end and start of the plan are merged, too
+        let input = "\
+        R 1 (#000000)
+        D 2 (#000000)
+        L 2 (#000000)
+        U 2 (#000000)
+        R 1 (#000000)";
+        assert_eq!(part1(input), 9);
+        assert_eq!(build_edges(parse_part1(input)).len(), 4);
+
+        // Split edges on the bottom and in the middle of a side
+        let input = "\
+        R 4 (#000000)
+        D 1 (#000000)
+        D 1 (#000000)
+        D 2 (#000000)
+        L 1 (#000000)
+        L 3 (#000000)
+        U 4 (#000000)";
+        assert_eq!(part1(input), 25);
+        assert_eq!(brute_area(parse_part1(input)), 25);
+    }
+}
@@ -20,8 +20,12 @@
 /// individual numbers, then running this new implementation on the input
 /// interval [1, 4000].
 
-use winnow::{stream::AsChar, ascii::alphanumeric1, token::take};
-use crate::utils::{*, parse::id};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::utils::*;
+#[cfg(any(test, feature = "dump-parsed"))]
+use crate::json::{ToJson, json_object};
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 enum Property {
@@ -31,6 +35,37 @@ enum Property {
     S,
 }
 
+/// All part properties, in the order they are listed in the input
+const PROPERTIES: [Property; 4] = [Property::X, Property::M, Property::A, Property::S];
+
+impl Property {
+    /// Name of the property in the input
+    pub fn name(&self) -> &'static str {
+        match self {
+            Property::X => "x",
+            Property::M => "m",
+            Property::A => "a",
+            Property::S => "s",
+        }
+    }
+}
+
+impl FromStr for Property {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        PROPERTIES.into_iter()
+            .find(|property| property.name() == input)
+            .ok_or_else(|| format!("Unknown part property: {}", input))
+    }
+}
+
+impl fmt::Display for Property {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 struct Part {
     pub x: I,
@@ -56,6 +91,59 @@ impl Part {
             Property::S => self.s = value,
         }
     }
+
+    /// Iterates over the properties of the part and their values, in the order
+    /// of PROPERTIES
+    pub fn iter(&self) -> impl Iterator<Item = (Property, I)> + '_ {
+        PROPERTIES.iter().map(|&property| (property, self.get(property)))
+    }
+}
+
+impl FromStr for Part {
+    type Err = String;
+
+    /// Parses a part definition (input looks like "{x=494,m=380,a=686,s=2820}")
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let property_list = input
+            .strip_prefix('{')
+            .and_then(|s| s.strip_suffix('}'))
+            .ok_or_else(|| format!("Part is not enclosed in braces: {}", input))?;
+
+        let mut part = Part { x: 0, m: 0, a: 0, s: 0 };
+        let mut seen = HashSet::<Property>::new();
+        for item in property_list.split(',') {
+            let (property_str, value_str) = item
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid part property: {}", item))?;
+            let property = property_str.parse::<Property>()?;
+            let value = value_str
+                .parse::<I>()
+                .map_err(|_| format!("Invalid value for part property: {}", item))?;
+            if !seen.insert(property) {
+                return Err(format!("Duplicate part property: {}", property_str));
+            }
+            part.set(property, value);
+        }
+
+        if seen.len() != 4 {
+            return Err(format!("Part is missing properties: {}", input));
+        }
+        Ok(part)
+    }
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let properties = self.iter().map(|(property, value)| format!("{}={}", property.name(), value)).join(",");
+        write!(f, "{{{}}}", properties)
+    }
+}
+
+#[cfg(any(test, feature = "dump-parsed"))]
+impl ToJson for Part {
+    fn to_json(&self) -> String {
+        json_object(&self.iter().map(|(property, value)| (property.name(), value.to_json())).vec())
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
@@ -64,6 +152,28 @@ enum Op {
     Gt,
 }
 
+impl FromStr for Op {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "<" => Ok(Op::Lt),
+            ">" => Ok(Op::Gt),
+            _ => Err(format!("Unknown operator: {}", input)),
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Op::Lt => write!(f, "<"),
+            Op::Gt => write!(f, ">"),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
 struct Rule {
     pub property: Property,
     pub op: Op,
@@ -71,84 +181,128 @@ struct Rule {
     pub send: String,
 }
 
+/// Formats the rule like in the input, e.g. "a<2006:qkq"
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}{}:{}", self.property, self.op, self.value, self.send)
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
 struct Workflow {
     pub name: String,
     pub rules: Vec<Rule>,
     pub default: String,
 }
 
-type Program = HashMap<String, Workflow>;
-
-/// Parses a line in the input into a workflow definition
-fn parse_workflow(input: &str) -> Workflow {
-    let workflow_str = input.replace("}", "");
-    let (name, rules) = workflow_str.split("{").pair();
-
-    let mut rule_items = rules.split(",").collect_vec();
-    let default = rule_items.pop().unwrap().to_owned();
-
-    Workflow {
-        name: name.to_owned(),
-        default,
-        rules: rule_items.iter().map(|rule_str| {
-            let (property, op, value_str, _, send) = 
-                (id, take(1usize), id, ":", id)
-                .parse(rule_str)
-                .unwrap();
-
-            Rule {
-                property: match property.as_str() {
-                    "x" => Property::X,
-                    "m" => Property::M,
-                    "a" => Property::A,
-                    "s" => Property::S,
-                    _ => unreachable!(),
-                },
-                op: match op {
-                    "<" => Op::Lt,
-                    ">" => Op::Gt,
-                    _ => unreachable!(),
-                },
-                value: value_str.parse::<I>().unwrap(),
-                send: send.to_owned(),
-            }
-        }).vec()
-    }
-}
-
-/// Parses a part definition (input looks like "{x=494,m=380,a=686,s=2820}")
-fn parse_part(input: &str) -> Part {
-    let mut part = Part { x: 0, m: 0, a: 0, s: 0 };
-    let property_list = input.replace("{", "").replace("}", "");
-    let items = property_list.split(',');
-    for item in items {
-        let (property, value_str) = parse::alphanums(item).pair();
-        let value = value_str.parse::<I>().unwrap();
-        match property.chars().next().unwrap() {
-            'x' => part.x = value,
-            'm' => part.m = value,
-            'a' => part.a = value,
-            's' => part.s = value,
-            _ => unreachable!(),
+#[cfg(any(test, feature = "dump-parsed"))]
+impl ToJson for Rule {
+    fn to_json(&self) -> String {
+        json_object(&[
+            ("property", self.property.to_string().to_json()),
+            ("op", self.op.to_string().to_json()),
+            ("value", self.value.to_json()),
+            ("send", self.send.to_json()),
+        ])
+    }
+}
+
+#[cfg(any(test, feature = "dump-parsed"))]
+impl ToJson for Workflow {
+    fn to_json(&self) -> String {
+        json_object(&[("name", self.name.to_json()), ("rules", self.rules.to_json()), ("default", self.default.to_json())])
+    }
+}
+
+/// Formats the workflow like in the input, e.g. "px{a<2006:qkq,m>2090:A,rfg}"
+impl fmt::Display for Workflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rules = self.rules.iter().map(|rule| format!("{},", rule)).join("");
+        write!(f, "{}{{{}{}}}", self.name, rules, self.default)
+    }
+}
+
+/// The workflows, by name
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+struct Program {
+    workflows: HashMap<String, Workflow>,
+}
+
+impl Program {
+    pub fn from_workflows(workflows: Vec<Workflow>) -> Program {
+        let mut program = Program::default();
+        for workflow in workflows {
+            program.add_workflow(workflow);
         }
+        program
+    }
+
+    /// Adds the workflow, replacing any previous workflow with the same name
+    pub fn add_workflow(&mut self, workflow: Workflow) {
+        self.workflows.insert(workflow.name.clone(), workflow);
+    }
+
+    pub fn get_workflow(&self, name: &str) -> Option<&Workflow> {
+        self.workflows.get(name)
+    }
+
+    /// Same as get_workflow, but with an error for unknown workflow names
+    fn workflow(&self, name: &str) -> Result<&Workflow, String> {
+        self.get_workflow(name).ok_or_else(|| format!("Unknown workflow: {}", name))
+    }
+}
+
+// Parsing functions for workflows (input looks like "px{a<2006:qkq,m>2090:A,rfg}")
+mod parse {
+    use super::*;
+    use crate::utils::parse::*;
+
+    pub fn property(input: &mut &str) -> PResult<Property> {
+        any.verify_map(|c: char| c.to_string().parse::<Property>().ok()).parse_next(input)
+    }
+
+    pub fn op(input: &mut &str) -> PResult<Op> {
+        any.verify_map(|c: char| c.to_string().parse::<Op>().ok()).parse_next(input)
+    }
+
+    /// Parses a single rule, like "a<2006:qkq"
+    pub fn rule(input: &mut &str) -> PResult<Rule> {
+        let (property, op, value, _, send) = (property, op, int, ':', id).parse_next(input)?;
+        Ok(Rule { property, op, value, send })
+    }
+
+    /// Parses a workflow. The rules are separated by commas, and the default
+    /// target follows after the last rule.
+    pub fn workflow(input: &mut &str) -> PResult<Workflow> {
+        let (name, _, rules, default, _) = (
+            id,
+            '{',
+            repeat(0.., terminated(rule, ',')),
+            id,
+            '}',
+        ).parse_next(input)?;
+        Ok(Workflow { name, rules, default })
     }
-    part
 }
 
 /// Parses the workflow and part lists
 fn parse(input: &str) -> (Program, Vec<Part>) {
-    let lines = input.lines().map(|line| line.trim()).vec();
-    let (workflows, parts) = lines.split(|line| line.trim().is_empty()).pair();
+    let (workflows, parts) = crate::utils::parse::blocks(input).into_iter().pair();
     
     (
-        HashMap::from_iter(workflows.iter().map(|workflow| {
-            let workflow = parse_workflow(workflow);
-            (workflow.name.clone(), workflow)
-        })),
-        parts.iter().map(|part| parse_part(part)).vec(),
+        Program::from_workflows(workflows.iter().map(|workflow| parse::workflow.parse(workflow).unwrap()).vec()),
+        parts.iter().map(|part| part.parse::<Part>().unwrap()).vec(),
     )
 }
 
+/// Parsed workflows (sorted by name) and parts as JSON, for debugging
+#[cfg(any(test, feature = "dump-parsed"))]
+pub fn dump_parsed(input: &str) -> String {
+    let (program, parts) = parse(input);
+    let workflows = program.workflows.into_values().sorted_by(|a, b| a.name.cmp(&b.name)).vec();
+    json_object(&[("workflows", workflows.to_json()), ("parts", parts.to_json())])
+}
+
 /// Evaluuates a rule condition on a given part
 pub fn part_fulfills_rule(part: &Part, rule: &Rule) -> bool {
     match rule.op {
@@ -157,9 +311,10 @@ pub fn part_fulfills_rule(part: &Part, rule: &Rule) -> bool {
     }
 }
 
-/// Applies the workflow rules to a given part
-pub fn process_part(part: &Part, program: &Program) -> bool {
-    let mut workflow = &program["in"];
+/// Applies the workflow rules to a given part. Returns true if the part is accepted,
+/// or an error if a rule sends the part to an unknown workflow.
+fn process_part(part: &Part, program: &Program) -> Result<bool, String> {
+    let mut workflow = program.workflow("in")?;
     loop {
         let mut send_to = &workflow.default as &str;
         for rule in &workflow.rules {
@@ -169,9 +324,9 @@ pub fn process_part(part: &Part, program: &Program) -> bool {
             }
         }
         match send_to {
-            "R" => { return false; },
-            "A" => { return true; },
-            _ => { workflow = &program[send_to]; },
+            "R" => { return Ok(false); },
+            "A" => { return Ok(true); },
+            _ => { workflow = program.workflow(send_to)?; },
         }
     }
 }
@@ -181,9 +336,9 @@ pub fn process_part(part: &Part, program: &Program) -> bool {
 pub fn part1(input: &str) -> I {
     let (program, parts) = parse(input);
     parts.iter()
-        .filter(|part| process_part(part, &program))   // Get the accepted parts
-        .map(|part| part.x + part.m + part.a + part.s) // Sum the numbers for each part
-        .sum()                                         // Sum the sums of all parts
+        .filter(|part| process_part(part, &program).unwrap())       // Get the accepted parts
+        .map(|part| part.iter().map(|(_, value)| value).sum::<I>()) // Sum the numbers for each part
+        .sum()                                                      // Sum the sums of all parts
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
@@ -221,39 +376,36 @@ fn get_sub_range_for_rule(range: PartRange, rule: &Rule) -> (Option<PartRange>,
     }
 }
 
-/// Calculate the number of distinct parts in the given range that are accepted by the given workflow
-fn get_range_combinations(mut range: PartRange, workflow_name: &str, program: &Program) -> i64 {
-    // "R" workflow always rejects
-    if workflow_name == "R" {
-        return 0;
-    } else if workflow_name == "A" {
-        // "A" workflow always accepts. The number of distinct accepted parts
-        // is the product of the lengths of the ranges for each part property.
-        let lengths = [
-            range.max.x - range.min.x + 1,
-            range.max.m - range.min.m + 1,
-            range.max.a - range.min.a + 1,
-            range.max.s - range.min.s + 1,
-        ];
-
-        return lengths.iter().product::<i64>();
+/// Calculate the number of distinct parts in the given range that end up in
+/// the given outcome ("A" or "R") when starting at the given workflow.
+/// Returns an error if any reachable rule refers to an unknown workflow.
+fn get_outcome_combinations(mut range: PartRange, workflow_name: &str, program: &Program, outcome: &str) -> Result<i64, String> {
+    if workflow_name == "A" || workflow_name == "R" {
+        if workflow_name != outcome {
+            return Ok(0);
+        }
+        // The number of distinct parts in the outcome is the product of the
+        // lengths of the ranges for each part property.
+        return Ok(range.min.iter().zip(range.max.iter())
+            .map(|((_, min), (_, max))| max - min + 1)
+            .product::<i64>());
     }
     
-    let workflow = &program[workflow_name];
+    let workflow = program.workflow(workflow_name)?;
 
-    // Accumulate the number of accepted combinations by splitting the ranges
+    // Accumulate the number of combinations by splitting the ranges
     // into the sub-ranges that pass or fail the rule conditions, then applying 
     // the appropriate following rules for those sub-ranges (by applying the
     // workflow defined by the rule for the passing sub-range, and the next rule
     // in the current workflow for the failing sub-range)
-    let mut accepted_combinations = 0i64;
+    let mut combinations = 0i64;
     for rule in &workflow.rules {
         // Split into true/false ranges (might be None if they are empty)
         let (maybe_true_range, maybe_false_range) = get_sub_range_for_rule(range, rule);
         
         // The true-range is sent to the workflow defined by the rule
         if let Some(true_range) = maybe_true_range {
-            accepted_combinations += get_range_combinations(true_range, &rule.send, program);
+            combinations += get_outcome_combinations(true_range, &rule.send, program, outcome)?;
         }
 
         // The false-range will be applied to the next rule in the current workflow
@@ -261,15 +413,25 @@ fn get_range_combinations(mut range: PartRange, workflow_name: &str, program: &P
             range = false_range;
         } else {
             // If the false range is empty, we can stop here
-            return accepted_combinations;
+            return Ok(combinations);
         }
     }
 
     // Send the remaining range to the default workflow
-    accepted_combinations += get_range_combinations(range, &workflow.default, program);
+    combinations += get_outcome_combinations(range, &workflow.default, program, outcome)?;
+
+    // Return the number of combinations
+    Ok(combinations)
+}
+
+/// Calculate the number of distinct parts in the given range that are accepted by the given workflow
+fn get_range_combinations(range: PartRange, workflow_name: &str, program: &Program) -> Result<i64, String> {
+    get_outcome_combinations(range, workflow_name, program, "A")
+}
 
-    // Return the number of accepted combinations
-    accepted_combinations
+/// Calculate the number of distinct parts in the given range that are rejected by the given workflow
+fn get_rejected_combinations(range: PartRange, workflow_name: &str, program: &Program) -> Result<i64, String> {
+    get_outcome_combinations(range, workflow_name, program, "R")
 }
 
 /// Part 2: Calculate the number of distinct parts in the range [1, 4000] that
@@ -280,13 +442,41 @@ pub fn part2(input: &str) -> I {
         min: Part { x: 1, m: 1, a: 1, s: 1 },
         max: Part { x: 4000, m: 4000, a: 4000, s: 4000 },
     };
-    get_range_combinations(initial_range, "in", &program)
+    let accepted = get_range_combinations(initial_range, "in", &program).unwrap();
+
+    // Every part is either accepted or rejected, so both counts must add up to
+    // the size of the initial range
+    if verbose() {
+        let rejected = get_rejected_combinations(initial_range, "in", &program).unwrap();
+        println!("Accepted: {}, rejected: {}, total: {}", accepted, rejected, accepted + rejected);
+        assert_eq!(accepted + rejected, 4000i64.pow(4));
+    }
+
+    accepted
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Counts the accepted parts in the cube [min, max]^4 by processing every
+    /// single part
+    fn count_accepted_brute(program: &Program, min: I, max: I) -> I {
+        let mut count = 0;
+        for x in min..=max {
+            for m in min..=max {
+                for a in min..=max {
+                    for s in min..=max {
+                        if process_part(&Part { x, m, a, s }, program).unwrap() {
+                            count += 1;
+                        }
+                    }
+                }
+            }
+        }
+        count
+    }
+
     #[test]
     fn test() {
         let input = "\
@@ -311,4 +501,137 @@ mod tests {
         assert_eq!(part1(input), 19114);
         assert_eq!(part2(input), 167409079868000);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_rejected_combinations() {
+        let input = "\
+        px{a<2006:qkq,m>2090:A,rfg}
+        pv{a>1716:R,A}
+        lnx{m>1548:A,A}
+        rfg{s<537:gd,x>2440:R,A}
+        qs{s>3448:A,lnx}
+        qkq{x<1416:A,crn}
+        crn{x>2662:A,R}
+        in{s<1351:px,qqz}
+        qqz{s>2770:qs,m<1801:hdj,R}
+        gd{a>3333:R,R}
+        hdj{m>838:A,pv}
+
+        {x=787,m=2655,a=1222,s=2876}";
+
+        // Every part is either accepted or rejected
+        let (program, _) = parse(input);
+        let range = PartRange {
+            min: Part { x: 1, m: 1, a: 1, s: 1 },
+            max: Part { x: 4000, m: 4000, a: 4000, s: 4000 },
+        };
+        let accepted = get_range_combinations(range, "in", &program).unwrap();
+        let rejected = get_rejected_combinations(range, "in", &program).unwrap();
+        assert_eq!(accepted, 167409079868000);
+        assert_eq!(accepted + rejected, 4000i64.pow(4));
+    }
+
+    #[test]
+    fn test_range_combinations_brute_force() {
+        let input = "\
+        in{x<10:px,m>15:R,qs}
+        px{a>5:A,s<3:R,qs}
+        qs{s>12:A,x>17:lnx,m<4:R,A}
+        lnx{a<8:R,m>10:A,R}
+
+        {x=1,m=1,a=1,s=1}";
+
+        let (program, _) = parse(input);
+        let range = PartRange {
+            min: Part { x: 1, m: 1, a: 1, s: 1 },
+            max: Part { x: 20, m: 20, a: 20, s: 20 },
+        };
+        assert_eq!(get_range_combinations(range, "in", &program), Ok(count_accepted_brute(&program, 1, 20)));
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        assert_eq!(parse::rule.parse("a<2006:qkq").unwrap(), Rule {
+            property: Property::A,
+            op: Op::Lt,
+            value: 2006,
+            send: "qkq".to_owned(),
+        });
+    }
+
+    #[test]
+    fn test_parse_workflow() {
+        // Target names that look like properties must not be confused with rules
+        let workflow = parse::workflow.parse("px{a<2006:a,m>2090:A,a}").unwrap();
+        assert_eq!(workflow.name, "px");
+        assert_eq!(workflow.rules.len(), 2);
+        assert_eq!(workflow.rules[0].send, "a");
+        assert_eq!(workflow.rules[1], Rule { property: Property::M, op: Op::Gt, value: 2090, send: "A".to_owned() });
+        assert_eq!(workflow.default, "a");
+
+        let workflow = parse::workflow.parse("a{s>3:s,x}").unwrap();
+        assert_eq!(workflow.name, "a");
+        assert_eq!(workflow.rules[0].send, "s");
+        assert_eq!(workflow.default, "x");
+    }
+
+    #[test]
+    fn test_part_iter() {
+        let part = Part { x: 787, m: 2655, a: 1222, s: 2876 };
+        assert_eq!(part.iter().collect_vec(), vec![
+            (Property::X, 787),
+            (Property::M, 2655),
+            (Property::A, 1222),
+            (Property::S, 2876),
+        ]);
+    }
+
+    #[test]
+    fn test_part_round_trip() {
+        for input in ["{x=787,m=2655,a=1222,s=2876}", "{x=1679,m=44,a=2067,s=496}"] {
+            assert_eq!(input.parse::<Part>().unwrap().to_string(), input);
+        }
+
+        assert_eq!("{x=1,m=2,a=3,s=4}".parse::<Part>(), Ok(Part { x: 1, m: 2, a: 3, s: 4 }));
+        assert!("{x=1,m=2,a=3}".parse::<Part>().is_err());
+        assert!("x=1,m=2,a=3,s=4".parse::<Part>().is_err());
+    }
+
+    #[test]
+    fn test_unknown_workflow() {
+        let workflow = |input: &str| parse::workflow.parse(input).unwrap();
+        let mut program = Program::from_workflows(vec![workflow("in{s<1351:px,A}")]);
+        assert!(program.get_workflow("in").is_some());
+        assert_eq!(program.get_workflow("px"), None);
+
+        // Parts and ranges that are sent to px fail, the others don't need it
+        let range = PartRange {
+            min: Part { x: 1, m: 1, a: 1, s: 1 },
+            max: Part { x: 10, m: 10, a: 10, s: 2000 },
+        };
+        assert_eq!(process_part(&Part { x: 1, m: 1, a: 1, s: 1 }, &program), Err("Unknown workflow: px".to_string()));
+        assert_eq!(process_part(&Part { x: 1, m: 1, a: 1, s: 2000 }, &program), Ok(true));
+        assert_eq!(get_range_combinations(range, "in", &program), Err("Unknown workflow: px".to_string()));
+        assert!(get_range_combinations(range, "nope", &program).unwrap_err().contains("nope"));
+
+        program.add_workflow(workflow("px{a>5:R,A}"));
+        assert_eq!(process_part(&Part { x: 1, m: 1, a: 1, s: 1 }, &program), Ok(true));
+        assert_eq!(get_range_combinations(range, "in", &program), Ok(10 * 10 * 5 * 1350 + 10 * 10 * 10 * 650));
+    }
+
+    #[test]
+    fn test_rule_round_trip() {
+        for input in ["a<2006:qkq", "m>2090:A", "s>3448:R", "x<1:a"] {
+            assert_eq!(parse::rule.parse(input).unwrap().to_string(), input);
+        }
+        for input in ["px{a<2006:qkq,m>2090:A,rfg}", "lnx{m>1548:A,A}", "end{R}"] {
+            assert_eq!(parse::workflow.parse(input).unwrap().to_string(), input);
+        }
+
+        assert_eq!("m".parse::<Property>(), Ok(Property::M));
+        assert!("q".parse::<Property>().is_err());
+        assert_eq!(">".parse::<Op>(), Ok(Op::Gt));
+        assert!("=".parse::<Op>().is_err());
+        assert!(parse::rule.parse("a=2006:qkq").is_err());
+    }
+}
@@ -24,10 +24,12 @@
 /// 
 /// I always dislike when I can only solve a subclass of the problem, but
 /// analyzing the input for clues is also fun. In this case, my solution should
-/// at least work for any input that has one gate behind rx with a set of
-/// conjunction gates behind it.
+/// at least work for any input that has one conjunction gate behind rx with a
+/// set of cycling subgraphs behind it. For other inputs, try_part2 returns an
+/// error instead of a wrong answer.
 
 use std::collections::VecDeque;
+use std::rc::Rc;
 
 use crate::utils::*;
 
@@ -39,25 +41,34 @@ enum ModuleType {
     Conjunction,
 }
 
-#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+// Module names are reference counted, so that sending a signal does not
+// need to allocate new strings.
+type Name = Rc<str>;
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
 struct Module {
-    pub name: String,
+    pub name: Name,
     pub module_type: ModuleType,
-    pub inputs: Vec<String>,
+    pub inputs: Vec<Name>,
     pub input_values: Vec<bool>,
-    pub outputs: Vec<String>,
+    pub outputs: Vec<Name>,
 }
 
 impl Module {
     pub fn set_input(&mut self, from_module: &str, value: bool) {
-        let index = self.inputs.iter().position(|x| x == from_module).unwrap();
+        let index = self.inputs.iter().position(|x| &**x == from_module).unwrap();
         self.input_values[index] = value;
     }
 }
 
-type Circuit = HashMap<String, Module>;
-// (from, to, value)
-type Signal = (String, String, bool);
+type Circuit = HashMap<Name, Module>;
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct Signal {
+    pub from: Name,
+    pub to: Name,
+    pub high: bool,
+}
 
 // Parsing functions
 // Trying out parser combinators today. Nice if you want good error messages, but too much work for AoC.
@@ -74,9 +85,9 @@ mod parse {
         })
     }
 
-    pub fn connections(input: &mut &str) -> PResult<Vec<String>> {
+    pub fn connections(input: &mut &str) -> PResult<Vec<Name>> {
         let connections: Vec::<&str> = separated(1.., take_while(1.., AsChar::is_alphanum), ", ").parse_next(input)?;
-        Ok(connections.iter().map(|c| c.to_string()).vec())
+        Ok(connections.iter().map(|&c| Name::from(c)).vec())
     }
 
     pub fn module(input: &mut &str) -> PResult<Module> {
@@ -89,19 +100,23 @@ mod parse {
 
         Ok(Module {
             module_type,
-            name: name.to_string(),
+            name: Name::from(name),
+            inputs: vec![],
+            input_values: vec![],
             outputs,
-            ..Default::default()
         })
     }
 
-    pub fn circuit(input: &str) -> Circuit {
-        let mut circuit = Circuit::from_iter(
-            input
+    /// Parses the circuit, returning an error with the line number and text of
+    /// the first line that is not a valid module, or if there is no broadcaster
+    pub fn try_parse(input: &str) -> Result<Circuit, String> {
+        let mut circuit = input
             .lines()
-            .map(|line| module.parse(line.trim()).unwrap())
-            .map(|module| (module.name.clone(), module))
-        );
+            .enumerate()
+            .map(|(i, line)| module.parse(line.trim())
+                .map(|module| (module.name.clone(), module))
+                .map_err(|e| format!("Invalid module on line {}: '{}'\n{}", i + 1, line.trim(), e)))
+            .collect::<Result<Circuit, String>>()?;
 
         // Connect outputs to the inputs
         for module in circuit.values().cloned().vec() {
@@ -113,22 +128,28 @@ mod parse {
             }
         }
 
-        circuit.get_mut("broadcaster").unwrap().inputs.push("button".to_string());
-        circuit.get_mut("broadcaster").unwrap().input_values.push(false);
+        // The button sends to the broadcaster
+        let broadcaster = circuit.get_mut("broadcaster").ok_or("The circuit has no broadcaster")?;
+        broadcaster.inputs.push(Name::from("button"));
+        broadcaster.input_values.push(false);
+
+        Ok(circuit)
+    }
 
-        circuit
+    pub fn circuit(input: &str) -> Circuit {
+        try_parse(input).unwrap()
     }
 }
 
-fn process_signal(circuit: &mut Circuit, (from_module, to_module, value): &Signal) -> Option<bool> {
-    if let Some(module) = circuit.get_mut(to_module) {
+fn process_signal(circuit: &mut Circuit, signal: &Signal) -> Option<bool> {
+    if let Some(module) = circuit.get_mut(&signal.to) {
         match module.module_type {
             ModuleType::Broadcast => {
-                Some(*value)
+                Some(signal.high)
             },
             ModuleType::FlipFlop => {
                 let state = module.input_values.get_mut(0).unwrap();
-                if !value {
+                if !signal.high {
                     *state = !*state;
                     Some(*state)
                 } else {
@@ -136,7 +157,7 @@ fn process_signal(circuit: &mut Circuit, (from_module, to_module, value): &Signa
                 }
             },
             ModuleType::Conjunction => {
-                module.set_input(&from_module, *value);
+                module.set_input(&signal.from, signal.high);
                 if module.input_values.iter().all(|&x| x) {
                     Some(false)
                 } else {
@@ -149,99 +170,132 @@ fn process_signal(circuit: &mut Circuit, (from_module, to_module, value): &Signa
     }
 }
 
-/// Part 1: simulate the circuit for 1000 button presses,
-/// count the number of low pulses emitted by any gate.
-pub fn part1(input: &str) -> I {
-    let mut circuit = parse::circuit(input);
+/// Simulates a single button press, calling on_signal for every signal that is
+/// sent through the circuit (including the initial button signal).
+fn press_button(circuit: &mut Circuit, mut on_signal: impl FnMut(&Signal)) {
+    let mut signals = VecDeque::<Signal>::new();
+    signals.push_back(Signal { from: Name::from("button"), to: Name::from("broadcaster"), high: false });
+    while let Some(signal) = signals.pop_front() {
+        on_signal(&signal);
+
+        let new_signal = process_signal(circuit, &signal);
+        if let Some(new_signal_value) = new_signal {
+            for output in &circuit[&signal.to].outputs {
+                signals.push_back(Signal { from: signal.to.clone(), to: output.clone(), high: new_signal_value });
+            }
+        }
+    }
+}
 
+/// Presses the button the given number of times and counts all low and high
+/// signals, returned as (low, high).
+fn count_signals(circuit: &mut Circuit, presses: I) -> (I, I) {
     let mut total_low_signals = 0i64;
     let mut total_high_signals = 0i64;
 
-    for _ in 0..1000 {
-        let mut signals = VecDeque::<Signal>::new();
-        signals.push_back(("button".to_string(), "broadcaster".to_string(), false));
-        while let Some(signal) = signals.pop_front() {
-            let module_name = &signal.1;
-
-            match signal.2 {
+    for _ in 0..presses {
+        press_button(circuit, |signal| {
+            match signal.high {
                 true => total_high_signals += 1,
                 false => total_low_signals += 1,
             }
-
-            let new_signal = process_signal(&mut circuit, &signal);
-            if let Some(new_signal_value) = new_signal {
-                for output in circuit[module_name].outputs.clone() {
-                    signals.push_back((module_name.clone(), output, new_signal_value));
-                }
-            }
-        }
+        });
     }
+    (total_low_signals, total_high_signals)
+}
+
+/// Part 1: simulate the circuit for 1000 button presses,
+/// count the number of low pulses emitted by any gate.
+pub fn part1(input: &str) -> I {
+    let mut circuit = parse::circuit(input);
+    let (total_low_signals, total_high_signals) = count_signals(&mut circuit, 1000);
     total_low_signals * total_high_signals
 }
 
+/// Upper limit for the number of button presses in part 2, so that we don't
+/// loop forever on circuits that don't have the expected structure.
+const MAX_BUTTON_PRESSES: I = 1_000_000;
+
 /// Part 2: Find the number of button presses required to get a low pulse from rx.
-pub fn part2(input: &str) -> I {
-    let mut circuit = parse::circuit(input);
+/// Returns an error if the circuit does not have the structure described in the
+/// module comment.
+pub fn try_part2(input: &str) -> Result<I, String> {
+    let mut circuit = parse::try_parse(input)?;
 
     // The module graph has a single conjunction that sends to rx, with a second
-    // layer of conjunctions that send to the first conjunction.
-    // Each conjunction in the second layer has a different cycle period in which
-    // they emit a high signal.
-    // As soon as all of the second-layer conjunctions send a high signal,
-    // the first conjunction will send a low signal to rx. To calculate the
-    // number of button presses needed, we take the least common multiple of the
-    // cycle periods of the second-layer conjunctions.
+    // layer of modules that send to the first conjunction.
+    // Each module in the second layer is the output of an independent subgraph
+    // that emits a high signal with a fixed cycle period.
+    // As soon as all of the second-layer modules send a high signal in the same
+    // button press, the first conjunction will send a low signal to rx. To
+    // calculate the number of button presses needed, we take the least common
+    // multiple of the cycle periods of the second-layer modules.
 
     // Get the conjunction that sends to rx
-    let conjunction_to_rx = circuit.values()
-        .find(|module| module.outputs.contains(&"rx".to_string()))
-        .unwrap()
-        .name
-        .clone();
-
-    // Get the conjunctions that send to the first conjunction
-    let second_level_conjunctions = circuit.values()
-        .filter(|module| module.outputs.contains(&conjunction_to_rx))
-        .map(|m| m.name.clone())
+    let rx_feeders = circuit.values()
+        .filter(|module| module.outputs.iter().any(|output| &**output == "rx"))
         .vec();
-    
-    // Press the button repeatedly. When one of the second-level conjunctions sends a high signal, record the cycle period.
-    // When we have all periods, return the least common multiple of them (the point where they all will send high at the same time)
-    let mut periods_for_second_level = HashMap::<String, I>::new();
-    for i in 1.. {
-        let mut signals = VecDeque::<Signal>::new();
-        signals.push_back(("button".to_string(), "broadcaster".to_string(), false));
-        while let Some(signal) = signals.pop_front() {
-            if signal.1 == "rx" && !signal.2 {
-                return i;
+    let conjunction_to_rx = match rx_feeders[..] {
+        [feeder] if feeder.module_type == ModuleType::Conjunction => feeder,
+        [] => return Err("No module sends to rx".to_string()),
+        [feeder] => return Err(format!("Module {} sends to rx, but is not a conjunction", feeder.name)),
+        _ => return Err(format!("Expected a single conjunction sending to rx, found {} modules", rx_feeders.len())),
+    };
+
+    // Get the modules that send to the first conjunction
+    let conjunction_name = conjunction_to_rx.name.clone();
+    let second_level = conjunction_to_rx.inputs.clone();
+
+    // Press the button repeatedly, and record the button presses at which each
+    // of the second-level modules sends a high signal to the conjunction.
+    // Two recorded presses are enough to know the cycle period.
+    let mut high_signal_presses = HashMap::<Name, Vec<I>>::new();
+    for presses in 1..=MAX_BUTTON_PRESSES {
+        let mut rx_received_low = false;
+        press_button(&mut circuit, |signal| {
+            if &*signal.to == "rx" && !signal.high {
+                rx_received_low = true;
+            }
+            if signal.high && signal.to == conjunction_name {
+                let recorded = high_signal_presses.entry(signal.from.clone()).or_default();
+                if recorded.last() != Some(&presses) {
+                    recorded.push(presses);
+                }
             }
+        });
+
+        if rx_received_low {
+            return Ok(presses);
+        }
 
-            let module_name = &signal.1;
-
-            let new_signal = process_signal(&mut circuit, &signal);
-            if let Some(new_signal_value) = new_signal {
-                for output in circuit[module_name].outputs.clone() {
-                    signals.push_back((module_name.clone(), output, new_signal_value));
-
-                    // Is one of the second-level conjunctions sending a high signal?
-                    // Then record the cycle period (the current number of iterations).
-                    if second_level_conjunctions.contains(module_name) && new_signal_value {
-                        periods_for_second_level.insert(module_name.to_string(), i);
-
-                        // Do we have all periods together? Then we can return the LCM.
-                        if periods_for_second_level.values().count() == second_level_conjunctions.len() {
-                            let mut solution = 1i64;
-                            for period in periods_for_second_level.values() {
-                                solution = lcm(solution, *period);
-                            }
-                            return solution;
-                        }
-                    }
+        // Do we have all periods together? Then we can return the LCM.
+        if second_level.iter().all(|name| high_signal_presses.get(name).is_some_and(|p| p.len() >= 2)) {
+            let mut solution = 1i64;
+            for name in &second_level {
+                let recorded = &high_signal_presses[name];
+                let period = recorded[1] - recorded[0];
+                // The LCM is only correct if the cycle starts with the first button press
+                if recorded[0] != period {
+                    return Err(format!(
+                        "Module {} does not have a clean cycle (first high signal after {} presses, then every {} presses)",
+                        name, recorded[0], period
+                    ));
                 }
+                solution = lcm(solution, period);
             }
+            return Ok(solution);
         }
     }
-    unreachable!()
+
+    Err(format!(
+        "Could not find the cycle periods of the modules sending to {} within {} button presses",
+        conjunction_name, MAX_BUTTON_PRESSES
+    ))
+}
+
+/// Part 2: Find the number of button presses required to get a low pulse from rx.
+pub fn part2(input: &str) -> I {
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
@@ -259,4 +313,136 @@ mod tests {
 
         assert_eq!(part1(input), 11687500);
     }
+
+    #[test]
+    fn test_signal_counts() {
+        let input = "\
+        broadcaster -> a, b, c
+        %a -> b
+        %b -> c
+        %c -> inv
+        &inv -> a";
+
+        assert_eq!(count_signals(&mut parse::circuit(input), 1), (8, 4));
+        assert_eq!(count_signals(&mut parse::circuit(input), 1000), (8000, 4000));
+
+        let input = "\
+        broadcaster -> a
+        %a -> inv, con
+        &inv -> b
+        %b -> con
+        &con -> output";
+
+        assert_eq!(count_signals(&mut parse::circuit(input), 1000), (4250, 2750));
+    }
+
+    #[test]
+    fn test_flip_flop_signals() {
+        let mut circuit = parse::circuit("broadcaster -> a\n%a -> b");
+        let low = Signal { from: Name::from("broadcaster"), to: Name::from("a"), high: false };
+        let high = Signal { high: true, ..low.clone() };
+
+        // Low signals toggle the flip-flop, high signals are ignored
+        assert_eq!(process_signal(&mut circuit, &low), Some(true));
+        assert_eq!(process_signal(&mut circuit, &high), None);
+        assert_eq!(process_signal(&mut circuit, &low), Some(false));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let input = "\
+        broadcaster -> a
+        %a b
+        &b -> a";
+
+        let error = parse::try_parse(input).unwrap_err();
+        assert!(error.contains("line 2"));
+        assert!(error.contains("%a b"));
+    }
+
+    #[test]
+    fn test_missing_broadcaster() {
+        let input = "\
+        %a -> inv
+        &inv -> rx";
+
+        assert!(parse::try_parse(input).unwrap_err().contains("broadcaster"));
+        assert!(try_part2(input).unwrap_err().contains("broadcaster"));
+    }
+
+    #[test]
+    fn test_part2_single_cycle() {
+        let input = "\
+        broadcaster -> a
+        %a -> inv
+        &inv -> con
+        &con -> rx";
+
+        assert_eq!(part2(input), 2);
+    }
+
+    #[test]
+    fn test_part2_two_cycles() {
+        // xa sends a high and then a low signal to con every 2 presses, xb every
+        // 3 presses (a counter that resets at 3, delayed by d1 and d2). The
+        // pulses never overlap, so rx never receives a low signal, and the
+        // answer has to come from the cycle periods.
+        let input = "\
+        broadcaster -> fa, b1
+        %fa -> xa, pa
+        &pa -> qa
+        &qa -> xa
+        %xa -> con
+        %b1 -> b2, hb
+        %b2 -> hb
+        &hb -> b1, d1
+        &d1 -> d2
+        &d2 -> xb
+        &xb -> con
+        &con -> rx";
+
+        assert_eq!(part2(input), 6);
+    }
+
+    #[test]
+    fn test_part2_offset_cycle() {
+        // xc sends its high signals at presses 1, 3, 5, ..., so its period of
+        // 2 presses doesn't start with the first press
+        let input = "\
+        broadcaster -> fa, fc
+        %fa -> xa, pa
+        &pa -> qa
+        &qa -> xa
+        %xa -> con
+        %fc -> pc
+        &pc -> xc, qc
+        &qc -> rc
+        &rc -> xc
+        %xc -> con
+        &con -> rx";
+
+        let error = try_part2(input).unwrap_err();
+        assert!(error.contains("xc does not have a clean cycle"), "{}", error);
+    }
+
+    #[test]
+    fn test_part2_without_rx() {
+        let input = "\
+        broadcaster -> a
+        %a -> inv, con
+        &inv -> b
+        %b -> con
+        &con -> output";
+
+        assert!(try_part2(input).unwrap_err().contains("rx"));
+    }
+
+    #[test]
+    fn test_part2_rx_without_conjunction() {
+        let input = "\
+        broadcaster -> a
+        %a -> rx";
+
+        assert!(try_part2(input).unwrap_err().contains("not a conjunction"));
+    }
 }
\ No newline at end of file
@@ -20,7 +20,7 @@
 
 use std::collections::VecDeque;
 
-use crate::{utils::*, vec2::Vec2};
+use crate::{utils::*, vec2::Vec2, grid::SparseGrid};
 
 /// Possible stepping directions
 const DIRECTIONS: [Vec2; 4] = [
@@ -30,30 +30,10 @@ const DIRECTIONS: [Vec2; 4] = [
     Vec2(-1, 0),
 ];
 
-/// The map, as a sparse set of obstacles
+/// The map, as a sparse set of floor tiles
 struct Map {
     pub start: Vec2,
-    pub floor_tiles: HashSet<Vec2>,
-    pub w: I,
-    pub h: I,
-}
-
-/// Accessor methods for the map (for infinite tiling)
-impl Map {
-    fn wrap_pos(&self, pos: Vec2) -> Vec2 {
-        let mut wrapped_pos = Vec2(pos.x() % self.w, pos.y() % self.h);
-        if wrapped_pos.0 < 0 {
-            wrapped_pos.0 += self.w;
-        }
-        if wrapped_pos.1 < 0 {
-            wrapped_pos.1 += self.h;
-        }
-        wrapped_pos
-    }
-
-    fn is_floor(&self, pos: Vec2) -> bool {
-        self.floor_tiles.contains(&self.wrap_pos(pos))
-    }
+    pub floor: SparseGrid,
 }
 
 /// Parses the map as a sparse set of obstacle positions
@@ -73,9 +53,11 @@ fn parse(input: &str) -> Map {
 
     Map {
         start: cells.iter().find(|(_, c)| *c == 'S').unwrap().0,
-        floor_tiles: cells.iter().filter(|(_, c)| *c != '#').map(|(pos, _)| *pos).collect(),
-        w: cells.iter().map(|(pos, _)| pos.x()).max().unwrap() + 1,
-        h: cells.iter().map(|(pos, _)| pos.y()).max().unwrap() + 1,
+        floor: SparseGrid::new(
+            cells.iter().filter(|(_, c)| *c != '#').map(|(pos, _)| *pos).collect(),
+            cells.iter().map(|(pos, _)| pos.x()).max().unwrap() + 1,
+            cells.iter().map(|(pos, _)| pos.y()).max().unwrap() + 1,
+        ),
     }
 }
 
@@ -84,7 +66,6 @@ fn parse(input: &str) -> Map {
 /// The infinite_map flag is needed to switch between part 1 and 2.
 fn solve_small(input: &str, steps: I, infinite_map: bool) -> I {
     let map = parse(input);
-    let floor_tiles = map.floor_tiles.clone();
 
     // BFS to find all cells within the given number of steps
     let mut queue = VecDeque::<(Vec2, I)>::new();
@@ -108,9 +89,9 @@ fn solve_small(input: &str, steps: I, infinite_map: bool) -> I {
                 let new_pos = pos + dir;
                 
                 let is_floor = if infinite_map {
-                    map.is_floor(new_pos)
+                    map.floor.is_set(new_pos)
                 } else {
-                    floor_tiles.contains(&new_pos)
+                    map.floor.base_contains(new_pos)
                 };
 
                 if is_floor && !visited.contains(&(new_pos, dist + 1)) {
@@ -124,17 +105,45 @@ fn solve_small(input: &str, steps: I, infinite_map: bool) -> I {
     count
 }
 
+/// Checks the assumptions of solve_large: The map is square, and the row and
+/// column of the start position, as well as the map border, have no obstacles.
+fn check_open_axes(map: &Map) -> bool {
+    let (w, h) = (map.floor.w, map.floor.h);
+    let is_open_row = |y: I| (0..w).all(|x| map.floor.base_contains(Vec2(x, y)));
+    let is_open_column = |x: I| (0..h).all(|y| map.floor.base_contains(Vec2(x, y)));
+
+    w == h
+        && is_open_row(map.start.y()) && is_open_column(map.start.x())
+        && is_open_row(0) && is_open_row(h - 1)
+        && is_open_column(0) && is_open_column(w - 1)
+}
+
+/// Maximum number of steps that solve_large simulates with solve_small when the
+/// extrapolation can't be used. The simulation explores every (position, steps)
+/// pair, so its cost grows with the cube of the step count. This covers the
+/// step counts from the puzzle description.
+const MAX_SIMULATED_STEPS: I = 500;
+
 /// Solution for large step counts, uses solve_small to simulate a few steps
 /// and extrapolates from there.
-fn solve_large(input: &str, steps: I) -> I {
+/// If the map does not fulfill the assumptions of the extrapolation (see
+/// check_open_axes), it falls back to the (slow) simulation of all steps for up
+/// to MAX_SIMULATED_STEPS, and returns an error for more steps.
+fn try_solve_large(input: &str, steps: I) -> Result<I, String> {
     let map = parse(input);
 
-    if steps < map.w * 2 {
-        return solve_small(input, steps, true);
+    if steps < map.floor.w * 2 {
+        return Ok(solve_small(input, steps, true));
+    }
+    if !check_open_axes(&map) {
+        if steps <= MAX_SIMULATED_STEPS {
+            return Ok(solve_small(input, steps, true));
+        }
+        return Err("map does not have open centre axes; quadratic extrapolation not applicable".to_string());
     }
 
-    println!("Map width: {}", map.w);
-    let map_width_offset = steps % map.w;
+    println!("Map width: {}", map.floor.w);
+    let map_width_offset = steps % map.floor.w;
 
     println!("Offset: {}", map_width_offset);
 
@@ -147,8 +156,8 @@ fn solve_large(input: &str, steps: I) -> I {
 
     let known = [
         map_width_offset,
-        map_width_offset + map.w,
-        map_width_offset + 2 * map.w,
+        map_width_offset + map.floor.w,
+        map_width_offset + 2 * map.floor.w,
     ].map(|step_count| (step_count as i128, solve_small(input, step_count, true) as i128)).to_vec();
 
     println!("Known: {:?}", known);
@@ -164,23 +173,44 @@ fn solve_large(input: &str, steps: I) -> I {
         }).sum::<i128>()
     };
     
-    extrapolated_polynomial(steps as i128) as i64
+    Ok(extrapolated_polynomial(steps as i128) as i64)
+}
+
+/// Reachable tiles after taking the given number of steps on the map
+pub fn part1_steps(input: &str, steps: I) -> I {
+    solve_small(input, steps, false)
+}
+
+/// Reachable tiles after taking the given number of steps on the infinitely
+/// repeating map, or an error if the map doesn't allow computing this for the
+/// given number of steps (see try_solve_large)
+pub fn try_part2_steps(input: &str, steps: I) -> Result<I, String> {
+    try_solve_large(input, steps)
+}
+
+/// Same as try_part2_steps, but panics on errors
+pub fn part2_steps(input: &str, steps: I) -> I {
+    try_part2_steps(input, steps).expect("Unable to count the reachable tiles")
 }
 
 /// Part 1: Reachable tiles after taking 64 steps
 pub fn part1(input: &str) -> I {
-    solve_small(input, 64, false)
+    part1_steps(input, 64)
 }
 
 /// Part 2: Reachable tiles after taking 26501365 steps
 pub fn part2(input: &str) -> I {
-    solve_large(input, 26501365)
+    try_part2_steps(input, 26501365).expect("Unable to solve part 2")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn solve_large(input: &str, steps: I) -> I {
+        try_solve_large(input, steps).unwrap()
+    }
+
     #[test]
     fn test() {
         let input = "\
@@ -199,6 +229,11 @@ mod tests {
             assert_eq!(solve_small(input, 1, false), 2);
             assert_eq!(solve_small(input, 2, false), 4);
             assert_eq!(solve_small(input, 6, false), 16);
+
+            // Step counts from the puzzle description
+            assert_eq!(part1_steps(input, 6), 16);
+            assert_eq!(part2_steps(input, 6), 16);
+            assert_eq!(part2_steps(input, 10), 50);
     }
 
     #[test]
@@ -261,4 +296,38 @@ mod tests {
             assert_eq!(solve_large(input, 60), solve_small(input, 60, true));
             assert_eq!(solve_large(input, 100), solve_small(input, 100, true));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_check_open_axes() {
+        // The example has obstacles on the start row
+        let input = "\
+            ...........
+            .....###.#.
+            .###.##..#.
+            ..#.#...#..
+            ....#.#....
+            .##..S####.
+            .##..#...#.
+            .......##..
+            .##.#.####.
+            .##..##.##.
+            ...........";
+
+        assert!(!check_open_axes(&parse(input)));
+
+        // So the extrapolation can't be used, and the steps are simulated instead.
+        // Step counts from the puzzle description:
+        assert_eq!(part2_steps(input, 50), 1594);
+        assert_eq!(part2_steps(input, 100), 6536);
+
+        // Too many steps to simulate
+        let error = try_part2_steps(input, MAX_SIMULATED_STEPS + 1).unwrap_err();
+        assert!(error.contains("open centre axes"), "{}", error);
+        assert!(try_part2_steps(input, 26501365).is_err());
+
+        // Open start row and column, but an obstacle on the border
+        assert!(check_open_axes(&parse("...\n.S.\n...")));
+        assert!(!check_open_axes(&parse("...\n.S.\n..#")));
+        assert!(!check_open_axes(&parse("....\n.S..\n....")));
+    }
+}
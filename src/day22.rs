@@ -16,7 +16,9 @@
 /// over the blocks in that order, so that when we determine how far a block
 /// falls, any blocks below it have already fallen.
 
-use crate::{utils::*, vec3::Vec3};
+use crate::{utils::*, vec3::Vec3, solution_import::SolutionWithContext};
+#[cfg(any(test, feature = "dump-parsed"))]
+use crate::json::{ToJson, json_object};
 
 /// Blocks have a min and a max position.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
@@ -38,113 +40,180 @@ fn parse(input: &str) -> Vec<Block> {
     }).vec()
 }
 
-/// Tests if two blocks intersect in the xy plane
-fn intersects_xy(a: &Block, b: &Block) -> bool {
-    // test if a and b intersect in the xy plane
-    a.min.x <= b.max.x     // a's left edge is left of b's right edge
-    && a.max.x >= b.min.x  // a's right edge is right of b's left edge
-    && a.min.y <= b.max.y  // a's top edge is above b's bottom edge
-    && a.max.y >= b.min.y  // a's bottom edge is below b's top edge
+#[cfg(any(test, feature = "dump-parsed"))]
+impl ToJson for Block {
+    fn to_json(&self) -> String {
+        json_object(&[("id", self.id.to_json()), ("min", self.min.to_json()), ("max", self.max.to_json())])
+    }
 }
 
-/// Lets all blocks fall down as far as they can, and returns true iff any
-/// block was moved. If check_only is true, it returns true iff any block
-/// **would** be moved, but does not actually move any blocks.
-fn gravity_step(blocks: &mut Vec<Block>, check_only: bool) -> bool {
-    let mut changed_something = false;
-
-    let max_z = blocks.iter().map(|other| other.max.z).max().unwrap();
-
-    // Group blocks by their top z coordinate, for fast finding of the blocks
-    // where other blocks will land on.
-    let blocks_by_z = (0..=max_z).map(|z| {
-        blocks.iter().cloned().filter(|block| block.max.z == z).collect_vec()
-    }).vec();
-
-    for i in 0..blocks.len() {
-        let block = blocks[i].clone();
-
-        // Find the closest Z coordinate below this block that contains a block where the
-        // xy part intersects with the current block (so that the current block will fall onto it)
-        let maybe_z_below = (1..block.min.z)
-            .rev()
-            .filter(|&z_below| {
-                blocks_by_z[z_below as usize]
-                    .iter()
-                    .any(|other_block| intersects_xy(&block, other_block))
-            })
-            .next();
-
-        if let Some(z_below) = maybe_z_below {
-            // Found a block that is below the current one, and intersects it in the xy plane.
-            // Unless the block is alredy directly above the block below, we move it down.
-            let delta = block.min.z - z_below - 1;
-            if delta > 0 {
-                if check_only {
-                    return true;
-                }
-                blocks[i].min.z -= delta;
-                blocks[i].max.z -= delta;
-                changed_something = true;
-            }
-        } else if block.min.z > 1 {
-            // If no block is below this one, and it is not already at the bottom, move it to the ground.
-            if check_only {
-                return true;
-            }
-            let delta = blocks[i].min.z - 1;
-            blocks[i].min.z -= delta;
-            blocks[i].max.z -= delta;
-            changed_something = true;
+/// Parsed blocks as JSON, for debugging
+#[cfg(any(test, feature = "dump-parsed"))]
+pub fn dump_parsed(input: &str) -> String {
+    parse(input).to_json()
+}
+
+/// Lets all blocks fall down as far as they can, and returns the number of
+/// blocks that moved.
+/// Blocks are processed in ascending z order, so all blocks that could be below
+/// a block have already settled. We track the top z coordinate of the settled
+/// blocks for each xy cell in a height map, so the resting height of each block
+/// can be looked up directly.
+fn settle(blocks: &mut [Block]) -> usize {
+    blocks.sort_by_key(|block| block.min.z);
+
+    let mut heights = HashMap::<(I, I), I>::new();
+    let mut moved = 0;
+
+    for block in blocks.iter_mut() {
+        let cells = (block.min.x ..= block.max.x).cartesian_product(block.min.y ..= block.max.y).vec();
+
+        // The block comes to rest directly above the highest settled block below it (or the ground)
+        let rest_z = cells.iter().map(|cell| heights.get(cell).copied().unwrap_or(0)).max().unwrap() + 1;
+        let delta = block.min.z - rest_z;
+        if delta > 0 {
+            block.min.z -= delta;
+            block.max.z -= delta;
+            moved += 1;
+        }
+
+        for cell in cells {
+            heights.insert(cell, block.max.z);
         }
     }
 
-    changed_something
+    moved
 }
 
-/// Part 1: How many blocks can be removed without moving any other block?
-pub fn part1(input: &str) -> I {
-    let mut blocks = parse(input);
-    blocks.sort_by_key(|block| block.min.z);
+/// Both parts need to know how many blocks move when removing each block from
+/// the settled stack, so this is computed once and shared between the parts.
+pub struct Day22;
 
-    while gravity_step(&mut blocks, false) {}
+/// For each block of the settled stack, the number of other blocks that move
+/// when it is removed
+pub struct SettledStack {
+    moved_if_removed: Vec<usize>,
+}
 
-    (0..blocks.len()).filter(|&i| {
-        let mut with_block_removed = blocks.iter().enumerate().filter(|&(j, _)| j != i).map(|(_, b)| *b).vec();
+impl SolutionWithContext for Day22 {
+    type Context = SettledStack;
 
-        !gravity_step(&mut with_block_removed, true)
-    }).count() as I
-}
+    fn context(input: &str) -> SettledStack {
+        let mut blocks = parse(input);
+        settle(&mut blocks);
 
-/// Part 2: How many blocks would move if a single block was removed?
-/// Sum this number for all blocks.
-pub fn part2(input: &str) -> I {
-    let mut blocks = parse(input);
-    blocks.sort_by_key(|block| block.min.z);
+        let moved_if_removed = (0..blocks.len()).map(|i| {
+            // Make a copy of the block stack, with the i-th block removed
+            let mut with_block_removed = blocks.clone();
+            with_block_removed.remove(i);
 
-    while gravity_step(&mut blocks, false) {}
+            // Let the blocks fall down and count how many moved
+            settle(&mut with_block_removed)
+        }).vec();
 
-    (0..blocks.len()).map(|i| {
-        // Make a copy of the block stack, with the i-th block removed
-        let mut with_block_removed = blocks.clone();
-        with_block_removed.remove(i);
+        SettledStack { moved_if_removed }
+    }
 
-        // Make a copy to compare with later
-        let old_blocks = with_block_removed.clone();
+    /// Part 1: How many blocks can be removed without moving any other block?
+    fn part1(stack: &SettledStack) -> I {
+        stack.moved_if_removed.iter().filter(|&&moved| moved == 0).count() as I
+    }
 
-        // Let the blocks fall down
-        while gravity_step(&mut with_block_removed, false) {}
+    /// Part 2: How many blocks would move if a single block was removed?
+    /// Sum this number for all blocks.
+    fn part2(stack: &SettledStack) -> I {
+        stack.moved_if_removed.iter().sum::<usize>() as I
+    }
+}
 
-        // Count how many blocks moved
-        old_blocks.iter()
-            .filter(|old| !with_block_removed.contains(old))
-            .count() as I
-    }).sum::<I>()
+/// Part 1: How many blocks can be removed without moving any other block?
+pub fn part1(input: &str) -> I {
+    Day22::part1(&Day22::context(input))
+}
+
+/// Part 2: How many blocks would move if a single block was removed?
+/// Sum this number for all blocks.
+pub fn part2(input: &str) -> I {
+    Day22::part2(&Day22::context(input))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::vec3::Axis;
+
+    /// Tests if two blocks overlap in 3D, i.e. if they intersect on all three axes.
+    /// Settled blocks must never overlap.
+    fn intersects_3d(a: &Block, b: &Block) -> bool {
+        Axis::ALL.iter().all(|&axis| {
+            a.min.get(axis) <= b.max.get(axis) && a.max.get(axis) >= b.min.get(axis)
+        })
+    }
+
+    /// Tests if two blocks intersect in the xy plane
+    fn intersects_xy(a: &Block, b: &Block) -> bool {
+        // test if a and b intersect in the xy plane
+        a.min.x <= b.max.x     // a's left edge is left of b's right edge
+        && a.max.x >= b.min.x  // a's right edge is right of b's left edge
+        && a.min.y <= b.max.y  // a's top edge is above b's bottom edge
+        && a.max.y >= b.min.y  // a's bottom edge is below b's top edge
+    }
+
+    /// Previous implementation of the settling, used as a reference for settle:
+    /// Lets all blocks fall down as far as they can, and returns true iff any
+    /// block was moved. If check_only is true, it returns true iff any block
+    /// **would** be moved, but does not actually move any blocks.
+    fn gravity_step(blocks: &mut Vec<Block>, check_only: bool) -> bool {
+        let mut changed_something = false;
+
+        let max_z = blocks.iter().map(|other| other.max.z).max().unwrap();
+
+        // Group blocks by their top z coordinate, for fast finding of the blocks
+        // where other blocks will land on.
+        let blocks_by_z = (0..=max_z).map(|z| {
+            blocks.iter().cloned().filter(|block| block.max.z == z).collect_vec()
+        }).vec();
+
+        for i in 0..blocks.len() {
+            let block = blocks[i].clone();
+
+            // Find the closest Z coordinate below this block that contains a block where the
+            // xy part intersects with the current block (so that the current block will fall onto it)
+            let maybe_z_below = (1..block.min.z)
+                .rev()
+                .filter(|&z_below| {
+                    blocks_by_z[z_below as usize]
+                        .iter()
+                        .any(|other_block| intersects_xy(&block, other_block))
+                })
+                .next();
+
+            if let Some(z_below) = maybe_z_below {
+                // Found a block that is below the current one, and intersects it in the xy plane.
+                // Unless the block is alredy directly above the block below, we move it down.
+                let delta = block.min.z - z_below - 1;
+                if delta > 0 {
+                    if check_only {
+                        return true;
+                    }
+                    blocks[i].min.z -= delta;
+                    blocks[i].max.z -= delta;
+                    changed_something = true;
+                }
+            } else if block.min.z > 1 {
+                // If no block is below this one, and it is not already at the bottom, move it to the ground.
+                if check_only {
+                    return true;
+                }
+                let delta = blocks[i].min.z - 1;
+                blocks[i].min.z -= delta;
+                blocks[i].max.z -= delta;
+                changed_something = true;
+            }
+        }
+
+        changed_something
+    }
 
     #[test]
     fn test_example() {
@@ -196,6 +265,90 @@ mod tests {
         assert_eq!(part2(input), 1);
     }
 
+    #[test]
+    fn test_settled_blocks_do_not_overlap() {
+        let input = "\
+        1,0,1~1,2,1
+        0,0,2~2,0,2
+        0,2,3~2,2,3
+        0,0,4~0,2,4
+        2,0,5~2,2,5
+        0,1,6~2,1,6
+        1,1,8~1,1,9";
+
+        let mut blocks = parse(input);
+        settle(&mut blocks);
+
+        for (a, b) in blocks.iter().tuple_combinations() {
+            assert!(!intersects_3d(a, b), "Blocks {} and {} overlap", a.id, b.id);
+        }
+
+        // Blocks that touch without overlapping are not intersecting
+        let a = Block { min: [0, 0, 1].into(), max: [2, 0, 1].into(), id: 0 };
+        let b = Block { min: [0, 0, 2].into(), max: [0, 2, 2].into(), id: 1 };
+        let c = Block { min: [1, 0, 1].into(), max: [1, 2, 3].into(), id: 2 };
+        assert!(!intersects_3d(&a, &b));
+        assert!(intersects_3d(&a, &c));
+        assert!(!intersects_3d(&b, &c));
+    }
+
+    #[test]
+    fn test_settle_matches_gravity_steps() {
+        let input = "\
+        1,0,1~1,2,1
+        0,0,2~2,0,2
+        0,2,3~2,2,3
+        0,0,4~0,2,4
+        2,0,5~2,2,5
+        0,1,6~2,1,6
+        1,1,8~1,1,9
+        5,5,3~5,5,7
+        4,5,12~6,5,12
+        0,0,20~9,9,20";
+
+        let mut settled = parse(input);
+        assert_eq!(settle(&mut settled), 8);
+        assert_eq!(settle(&mut settled), 0);
+
+        let mut stepped = parse(input);
+        stepped.sort_by_key(|block| block.min.z);
+        while gravity_step(&mut stepped, false) {}
+
+        settled.sort_by_key(|block| block.id);
+        stepped.sort_by_key(|block| block.id);
+        assert_eq!(settled, stepped);
+    }
+
+    #[test]
+    fn test_shared_context() {
+        let input = "\
+        1,0,1~1,2,1
+        0,0,2~2,0,2
+        0,2,3~2,2,3
+        0,0,4~0,2,4
+        2,0,5~2,2,5
+        0,1,6~2,1,6
+        1,1,8~1,1,9
+        5,5,3~5,5,7
+        4,5,12~6,5,12";
+
+        // Independent computation of both parts, settling the stack separately
+        let independent = |count: fn(usize) -> I| {
+            let mut blocks = parse(input);
+            settle(&mut blocks);
+            (0..blocks.len()).map(|i| {
+                let mut with_block_removed = blocks.clone();
+                with_block_removed.remove(i);
+                count(settle(&mut with_block_removed))
+            }).sum::<I>()
+        };
+        let part1_independent = independent(|moved| if moved == 0 { 1 } else { 0 });
+        let part2_independent = independent(|moved| moved as I);
+
+        assert_eq!(solution_import::run_with_context::<Day22>(input), (part1_independent, part2_independent));
+        assert_eq!((part1(input), part2(input)), (part1_independent, part2_independent));
+    }
+
     #[test]
     fn test_parse() {
         let input = "\
@@ -224,4 +377,11 @@ mod tests {
 
         assert_eq!(parse(input), expected_blocks);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_block_to_json() {
+        let block = Block { min: [1, 0, 1].into(), max: [1, 2, 1].into(), id: 0 };
+        assert_eq!(block.to_json(), r#"{"id":0,"min":{"x":1,"y":0,"z":1},"max":{"x":1,"y":2,"z":1}}"#);
+        assert_eq!(dump_parsed("1,0,1~1,2,1"), format!("[{}]", block.to_json()));
+    }
+}
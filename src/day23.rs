@@ -50,15 +50,15 @@ fn get_neighbors(map: &Map, pos: Vec2, respect_slopes: bool) -> Vec<Vec2> {
 }
 
 /// Grid-based solution for finding the longest path
-fn longest_path(map: &Map, start: Vec2, goal: Vec2, visited: &mut HashSet<Vec2>) -> Option<I> {
-    get_neighbors(map, start, true).map(|&next_pos| {
+fn longest_path(map: &Map, start: Vec2, goal: Vec2, visited: &mut HashSet<Vec2>, respect_slopes: bool) -> Option<I> {
+    get_neighbors(map, start, respect_slopes).map(|&next_pos| {
         if next_pos == goal {
             Some(1)
         } else if visited.contains(&next_pos) {
             None
         } else {
             visited.insert(next_pos);
-            let longest_length = longest_path(map, next_pos, goal, visited).map(|length| length + 1);
+            let longest_length = longest_path(map, next_pos, goal, visited, respect_slopes).map(|length| length + 1);
             visited.remove(&next_pos);
             longest_length
         }
@@ -75,27 +75,79 @@ pub fn part1(input: &str) -> I {
     let &end = map.keys().max_by_key(|pos| pos.y()).unwrap();
     
     // Find the longest path and return its length.
-    longest_path(&map, start, end, &mut HashSet::new()).unwrap()
+    longest_path(&map, start, end, &mut HashSet::new(), true).unwrap()
 }
 
 /// Graph-based map: (intersection points => list of connected intersections)
 type GraphMap = HashMap::<Vec2, Vec<(Vec2, I)>>;
 
-/// Graph-based solution for finding the longest path
-fn longest_path_graph(map: &GraphMap, start: Vec2, goal: Vec2, visited: &mut HashSet<Vec2>) -> Option<I> {
-    let neighbors = map.get(&start).unwrap();
-    neighbors.map(|&(next_pos, len)| {
-        if next_pos == goal {
-            Some(len)
-        } else if visited.contains(&next_pos) {
-            None
-        } else {
-            visited.insert(next_pos);
-            let longest_length = longest_path_graph(map, next_pos, goal, visited).map(|length| length + len);
-            visited.remove(&next_pos);
-            longest_length
+/// Graph-based solution for finding the longest path, using branch-and-bound:
+/// A path can enter each unvisited node at most once, so the sum of the longest
+/// edge into each unvisited node is an upper bound for the remaining length.
+/// Branches that can't beat the best path found so far, even with that bound,
+/// are cut.
+fn longest_path_graph(map: &GraphMap, start: Vec2, goal: Vec2) -> Option<I> {
+    let longest_edge_into = HashMap::<Vec2, I>::from_iter(map.keys().map(|&node| {
+        let longest = map.values().flatten().filter(|&&(to, _)| to == node).map(|&(_, len)| len).max().unwrap_or(0);
+        (node, longest)
+    }));
+    let bound = longest_edge_into.iter().filter(|&(&node, _)| node != start).map(|(_, &len)| len).sum();
+
+    let mut search = GraphSearch { map, goal, longest_edge_into, visited: HashSet::from([start]), best: None };
+    search.search(start, 0, bound);
+    search.best
+}
+
+/// State of the branch-and-bound search in longest_path_graph
+struct GraphSearch<'a> {
+    map: &'a GraphMap,
+    goal: Vec2,
+    longest_edge_into: HashMap<Vec2, I>,
+    visited: HashSet<Vec2>,
+    /// Length of the longest path to the goal found so far
+    best: Option<I>,
+}
+
+impl GraphSearch<'_> {
+    /// Searches all paths from pos to the goal. length is the length of the
+    /// path up to pos, bound the upper bound for the remaining length.
+    fn search(&mut self, pos: Vec2, length: I, bound: I) {
+        for &(next_pos, len) in &self.map[&pos] {
+            if next_pos == self.goal {
+                self.best = self.best.max(Some(length + len));
+            } else if !self.visited.contains(&next_pos) {
+                // Cut the branch if it can't beat the best path, even in the best case
+                let next_bound = bound - self.longest_edge_into[&next_pos];
+                if self.best.is_some_and(|best| length + len + next_bound <= best) {
+                    continue;
+                }
+
+                self.visited.insert(next_pos);
+                self.search(next_pos, length + len, next_bound);
+                self.visited.remove(&next_pos);
+            }
         }
-    }).flatten().max()
+    }
+}
+
+/// Follows the corridor starting at the intersection from, with the first step
+/// to first, until reaching another intersection. Returns the intersection, the
+/// tile before it, and the length of the corridor, or None for dead ends.
+fn walk_corridor(map: &Map, crossings: &HashSet<Vec2>, from: Vec2, first: Vec2) -> Option<(Vec2, Vec2, I)> {
+    let mut prev = from;
+    let mut pos = first;
+    let mut length = 1;
+
+    // Keep following the path until we arrive at another intersection
+    while !crossings.contains(&pos) {
+        // Find the one accessible neighbor that is not the previous position
+        let next = get_neighbors(map, pos, false).into_iter().find(|&next| next != prev)?;
+        prev = pos;
+        pos = next;
+        length += 1;
+    }
+
+    Some((pos, prev, length))
 }
 
 fn build_graph_map(map: &Map) -> GraphMap {
@@ -107,47 +159,32 @@ fn build_graph_map(map: &Map) -> GraphMap {
     let crossings = map
         .keys()
         .copied()
-        .filter(|&pos| get_neighbors(&map, pos, false).len() > 2 || pos == start || pos == end)
-        .vec();
+        .filter(|&pos| get_neighbors(map, pos, false).len() > 2 || pos == start || pos == end)
+        .collect::<HashSet<_>>();
+
+    // Contracted corridors, keyed by the intersection and the first step into the corridor.
+    // Each corridor is walked only once, and stored for both directions.
+    let mut corridors = HashMap::<(Vec2, Vec2), (Vec2, I)>::new();
+    for &crossing_point in &crossings {
+        for first in get_neighbors(map, crossing_point, false) {
+            if corridors.contains_key(&(crossing_point, first)) {
+                continue;
+            }
+            if let Some((other, last, length)) = walk_corridor(map, &crossings, crossing_point, first) {
+                corridors.insert((crossing_point, first), (other, length));
+                let reverse = corridors.insert((other, last), (crossing_point, length));
+                assert!(reverse.is_none() || (other, last) == (crossing_point, first), "Corridor from {:?} walked twice", other);
+            }
+        }
+    }
 
     // Build a graph of the maze, with the intersections as nodes and the
     // pathways between them as edges
-    GraphMap::from_iter(
-        // For each intersection point, get the connected intersections
-        // and the length of the path between them.
-        crossings.clone().into_iter().map(|crossing_point| {
-                let neighbors = get_neighbors(&map, crossing_point, false);
-                // Follow the four directions starting at the intersection,
-                // until we find another intersection (or a dead end, which we ignore)
-                let connected_crossings = neighbors.iter().map(|&neighbor| {
-                    let mut prev = crossing_point;
-                    let mut pos = neighbor;
-                    let mut length = 1;
-                    loop {
-                        // Did we arrive at another intersection?
-                        if crossings.contains(&pos) {
-                            return Some((pos, length));
-                        }
-                        else {
-                            // Otherwise, keep following the path
-                            let neighbors = get_neighbors(&map, pos, false);
-                            // Find the one accessible neighbor that is not the previous position
-                            let maybe_next = neighbors.iter().filter(|&&next| next != prev).next();
-                            if let Some(&next) = maybe_next {
-                                prev = pos;
-                                pos = next;
-                                length += 1;
-                            } else {
-                                // No more neighbors, we reached a dead end
-                                return None;
-                            }
-                        }
-                    }
-                }).flatten().vec();
-
-                (crossing_point, connected_crossings)
-        })
-    )
+    let mut graph = GraphMap::from_iter(crossings.iter().map(|&crossing_point| (crossing_point, vec![])));
+    for ((crossing_point, _), edge) in corridors {
+        graph.get_mut(&crossing_point).unwrap().push(edge);
+    }
+    graph
 }
 
 /// Part 2: Find the longest path, while only accessing each tile once.
@@ -163,13 +200,62 @@ pub fn part2(input: &str) -> I {
     let graph = build_graph_map(&map);
 
     // Find the longest path based on the graph, return its length
-    longest_path_graph(&graph, start, end, &mut HashSet::new()).unwrap()
+    longest_path_graph(&graph, start, end).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Graph-based longest path search without pruning, as a reference for longest_path_graph
+    fn longest_path_graph_unpruned(map: &GraphMap, start: Vec2, goal: Vec2, visited: &mut HashSet<Vec2>) -> Option<I> {
+        let neighbors = map.get(&start).unwrap();
+        neighbors.map(|&(next_pos, len)| {
+            if next_pos == goal {
+                Some(len)
+            } else if visited.contains(&next_pos) {
+                None
+            } else {
+                visited.insert(next_pos);
+                let longest_length = longest_path_graph_unpruned(map, next_pos, goal, visited).map(|length| length + len);
+                visited.remove(&next_pos);
+                longest_length
+            }
+        }).flatten().max()
+    }
+
+    /// Grid-based solution for part 2, as a reference for the graph contraction
+    fn longest_path_grid_without_slopes(map: &Map) -> Option<I> {
+        let &start = map.keys().min_by_key(|pos| pos.y()).unwrap();
+        let &end = map.keys().max_by_key(|pos| pos.y()).unwrap();
+        longest_path(map, start, end, &mut HashSet::from([start]), false)
+    }
+
+    const EXAMPLE: &str = "\
+        #.#####################
+        #.......#########...###
+        #######.#########.#.###
+        ###.....#.>.>.###.#.###
+        ###v#####.#v#.###.#.###
+        ###.>...#.#.#.....#...#
+        ###v###.#.#.#########.#
+        ###...#.#.#.......#...#
+        #####.#.#.#######.#.###
+        #.....#.#.#.......#...#
+        #.#####.#.#.#########v#
+        #.#...#...#...###...>.#
+        #.#.#v#######v###.###v#
+        #...#.>.#...>.>.#.###.#
+        #####v#.#.###v#.#.###.#
+        #.....#...#...#.#.#...#
+        #.#########.###.#.#.###
+        #...###...#...#...#.###
+        ###.###.#.###v#####v###
+        #...#...#.#.>.>.#.>.###
+        #.###.###.#.###.#.#v###
+        #.....###...###...#...#
+        #####################.#";
+
     #[test]
     fn test_example() {
         let input = "\
@@ -200,4 +286,66 @@ mod tests {
             assert_eq!(part1(input), 94);
             assert_eq!(part2(input), 154);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_pruning_keeps_result() {
+        let map = parse(EXAMPLE);
+        let graph = build_graph_map(&map);
+        let start = Vec2(1, 0);
+        let end = Vec2(21, 22);
+
+        assert_eq!(longest_path_graph_unpruned(&graph, start, end, &mut HashSet::new()), Some(154));
+        assert_eq!(longest_path_graph(&graph, start, end), Some(154));
+
+        // Also for the path to an intersection in the middle of the maze
+        let middle = Vec2(13, 13);
+        assert!(graph.contains_key(&middle));
+        assert_eq!(
+            longest_path_graph(&graph, start, middle),
+            longest_path_graph_unpruned(&graph, start, middle, &mut HashSet::new())
+        );
+    }
+
+    #[test]
+    fn test_graph_edges() {
+        let graph = build_graph_map(&parse(EXAMPLE));
+
+        // Start, end, and the intersections between them
+        let nodes = [(1, 0), (11, 3), (3, 5), (21, 11), (5, 13), (13, 13), (13, 19), (19, 19), (21, 22)];
+        assert_eq!(graph.keys().map(|&Vec2(x, y)| (x, y)).sorted().collect_vec(), nodes.iter().copied().sorted().collect_vec());
+
+        // Each corridor as (from, to, length), in both directions
+        let corridors = [
+            ((1, 0), (3, 5), 15),
+            ((3, 5), (11, 3), 22),
+            ((3, 5), (5, 13), 22),
+            ((11, 3), (21, 11), 30),
+            ((11, 3), (13, 13), 24),
+            ((21, 11), (13, 13), 18),
+            ((21, 11), (19, 19), 10),
+            ((5, 13), (13, 13), 12),
+            ((5, 13), (13, 19), 38),
+            ((13, 13), (13, 19), 10),
+            ((13, 19), (19, 19), 10),
+            ((19, 19), (21, 22), 5),
+        ];
+        let expected_edges = corridors.iter()
+            .flat_map(|&(a, b, length)| [(a, b, length), (b, a, length)])
+            .sorted()
+            .collect_vec();
+        let edges = graph.iter()
+            .flat_map(|(&Vec2(x, y), edges)| edges.iter().map(move |&(Vec2(to_x, to_y), length)| ((x, y), (to_x, to_y), length)))
+            .sorted()
+            .collect_vec();
+        assert_eq!(edges, expected_edges);
+    }
+
+    #[test]
+    fn test_grid_matches_graph() {
+        let map = parse(EXAMPLE);
+        let graph = build_graph_map(&map);
+
+        assert_eq!(longest_path_grid_without_slopes(&map), Some(154));
+        assert_eq!(longest_path_graph(&graph, Vec2(1, 0), Vec2(21, 22)), Some(154));
+    }
+}
@@ -33,14 +33,29 @@ use core::panic;
 use crate::utils::*;
 use crate::vec2_128::Vec2L;
 use crate::vec3_128::Vec3L;
+#[cfg(any(test, feature = "dump-parsed"))]
+use crate::json::{ToJson, json_object};
 
 /// Hailstones are rays,
 /// defined by a position and a velocity vector
-type Ray = (Vec3L, Vec3L);
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Ray {
+    pub pos: Vec3L,
+    pub vel: Vec3L,
+}
 
-/// Evaluates a hailstone position for the given time
-fn eval_ray(ray: Ray, t: i128) -> Vec3L {
-    ray.0 + ray.1 * t
+impl Ray {
+    /// Evaluates the hailstone position for the given time
+    pub fn at(&self, t: i128) -> Vec3L {
+        self.pos + self.vel * t
+    }
+}
+
+#[cfg(any(test, feature = "dump-parsed"))]
+impl ToJson for Ray {
+    fn to_json(&self) -> String {
+        json_object(&[("pos", self.pos.to_json()), ("vel", self.vel.to_json())])
+    }
 }
 
 /// Parses a hailstone ray.
@@ -50,7 +65,7 @@ fn parse_line(line: &str) -> Ray {
         .split(|c| c == ',' || c == '@')
         .map(|part| part.trim().parse::<i128>().unwrap())
         .vec();
-    (items[0..3].into(), items[3..6].into())
+    Ray { pos: items[0..3].into(), vel: items[3..6].into() }
 }
 
 /// Parses the list of hailstones
@@ -58,6 +73,12 @@ fn parse(input: &str) -> Vec<Ray> {
     input.lines().map(parse_line).vec()
 }
 
+/// Parsed hailstones as JSON, for debugging
+#[cfg(any(test, feature = "dump-parsed"))]
+pub fn dump_parsed(input: &str) -> String {
+    parse(input).to_json()
+}
+
 /// Tests if two rays intersect in the xy plane.
 /// Returns the intersection point if they do.
 fn ray_intersection_2d((ap, ad): (Vec2L, Vec2L), (bp, bd): (Vec2L, Vec2L)) -> Option<Vec2L> {
@@ -82,16 +103,51 @@ fn ray_intersection_2d((ap, ad): (Vec2L, Vec2L), (bp, bd): (Vec2L, Vec2L)) -> Op
     }
 }
 
+/// Exact version of ray_intersection_2d, using only integer arithmetic.
+/// Returns the intersection point as a rational point (numerator, denominator),
+/// i.e. the actual point is numerator / denominator. The denominator is always
+/// positive, so the point can be compared against bounds without division.
+fn ray_intersection_2d_exact((ap, ad): (Vec2L, Vec2L), (bp, bd): (Vec2L, Vec2L)) -> Option<(Vec2L, i128)> {
+    let d = bp - ap;
+    let det = bd.cross(ad);
+
+    if det == 0 {
+        return None;
+    }
+
+    // The intersection is at ap + ad * u = bp + bd * v, with u = u_num / det and v = v_num / det
+    let u_num = d.y() * bd.x() - d.x() * bd.y();
+    let v_num = d.y() * ad.x() - d.x() * ad.y();
+
+    // The intersection must not be in the past of either ray, i.e. u and v must not be negative
+    if u_num.signum() * det.signum() < 0 || v_num.signum() * det.signum() < 0 {
+        return None;
+    }
+
+    // Make the denominator positive
+    let (u_num, det) = if det < 0 { (-u_num, -det) } else { (u_num, det) };
+    Some((ap * det + ad * u_num, det))
+}
+
+/// Tests if a rational point (numerator, positive denominator), as returned by
+/// ray_intersection_2d_exact, is inside the closed test area.
+/// Since the denominator is positive, min <= p / den <= max is the same as
+/// min * den <= p <= max * den, so we don't need to round the point.
+fn is_in_area((p, den): (Vec2L, i128), area_min: Vec2L, area_max: Vec2L) -> bool {
+    p.x() >= area_min.x() * den && p.x() <= area_max.x() * den &&
+    p.y() >= area_min.y() * den && p.y() <= area_max.y() * den
+}
+
 /// Tests if two rays intersect in 3D space
-fn intersect_ray_3d((p1, d1): Ray, (p2, d2): Ray) -> bool {
+fn intersect_ray_3d(a: Ray, b: Ray) -> bool {
     // We want to find a time t when the two rays intersect.
     // Solving this equation for t:
     // p1 + d1 * t = p2 + d2 * t
     // => p1 - p2 = (d2 - d1) * t
     // => t = (p1 - p2) / (d2 - d1)
 
-    let pd = p1 - p2;
-    let dd = d2 - d1;
+    let pd = a.pos - b.pos;
+    let dd = b.vel - a.vel;
 
     // Try to find one axis where we can compute t = (p1 - p2) / (d2 - d1)
     // (we only need one axis, but if d2 - d1 is zero on that axis we can't use it)
@@ -104,7 +160,7 @@ fn intersect_ray_3d((p1, d1): Ray, (p2, d2): Ray) -> bool {
     // that the rays really do intersect at that point)
     if let Some(t) = maybe_t {
         t >= 0
-        && p1 + t * d1 == p2 + t * d2
+        && a.at(t) == b.at(t)
     } else {
         false
     }
@@ -112,7 +168,7 @@ fn intersect_ray_3d((p1, d1): Ray, (p2, d2): Ray) -> bool {
 
 /// Finds the rock trajectory that hits all hailstones.
 /// Returns the position of the rock at t=0.
-fn solve_rock_trajectory(rays: Vec<(Vec3L, Vec3L)>) -> Vec3L {
+fn solve_rock_trajectory(rays: Vec<Ray>) -> Vec3L {
     // Iterate over all possible velocity vectors of the rock.
     // Start by looking only at x/y coordinates.
     for x in -1000..1000 {
@@ -125,7 +181,7 @@ fn solve_rock_trajectory(rays: Vec<(Vec3L, Vec3L)>) -> Vec3L {
 
             let mut shifted_rays = rays.iter()
                 .enumerate()
-                .map(|(i, &(p, d))| (i, (Vec2L(p.x, p.y), Vec2L(d.x, d.y) - rock_velocity)));
+                .map(|(i, ray)| (i, (ray.pos.xy(), ray.vel.xy() - rock_velocity)));
 
             let (_, ray1) = shifted_rays.next().unwrap();
             while let Some((ray2_index, ray2)) = shifted_rays.next() {
@@ -141,8 +197,8 @@ fn solve_rock_trajectory(rays: Vec<(Vec3L, Vec3L)>) -> Vec3L {
                         let pd2 = intersection_candidate - ray2.0;
                         let t2 = pd2.x().checked_div(ray2.1.x()).or(pd2.y().checked_div(ray2.1.y())).unwrap();
 
-                        let intersection1_3d = eval_ray(rays[0], t1);
-                        let intersection2_3d = eval_ray(rays[ray2_index], t2);
+                        let intersection1_3d = rays[0].at(t1);
+                        let intersection2_3d = rays[ray2_index].at(t2);
 
                         let rock_velocity_3d = (intersection1_3d - intersection2_3d) / (t1 - t2);
                         let rock_position = intersection1_3d - rock_velocity_3d * t1;
@@ -157,43 +213,53 @@ fn solve_rock_trajectory(rays: Vec<(Vec3L, Vec3L)>) -> Vec3L {
     panic!("No solution found");
 }
 
-fn count_collisions_in_area(stones: Vec<Ray>, test_area_min: Vec2L, test_area_max: Vec2L) -> I {
+/// Counts the hailstone pairs with trajectories intersecting inside the test area
+/// (in the xy plane)
+pub fn count_collisions_in_area(stones: Vec<Ray>, test_area_min: Vec2L, test_area_max: Vec2L) -> I {
 
     // Only look at the xy plane
-    let stones_2d = stones.map(|&(pos, dir)| (pos.xy(), dir.xy())).vec();
+    let stones_2d = stones.map(|stone| (stone.pos.xy(), stone.vel.xy())).vec();
 
     // Iterate over all hailstone pairs
     stones_2d.iter().tuple_combinations()
         // Find the ones that intersect
-        .map(|(&a, &b)| ray_intersection_2d(a, b))
-        .flatten()
+        .filter_map(|(&a, &b)| ray_intersection_2d_exact(a, b))
         // Find the intersections that are inside the test area
-        .filter(|p| {
-            p.x() >= test_area_min.x() && p.x() <= test_area_max.x() &&
-            p.y() >= test_area_min.y() && p.y() <= test_area_max.y()
-        })
+        .filter(|&p| is_in_area(p, test_area_min, test_area_max))
         .count() as I
 }
 
 /// Part 1: Find the number of hailstone pairs with intersecting trajectories
 /// inside the 2D test area.
 pub fn part1(input: &str) -> I {
-    let stones = parse(input);
-    let test_area_min = Vec2L(200000000000000, 200000000000000);
-    let test_area_max = Vec2L(400000000000000, 400000000000000);
-    count_collisions_in_area(stones, test_area_min, test_area_max)
+    part1_with_area(input, 200000000000000, 400000000000000)
+}
+
+/// Part 1 with a custom test area, where both x and y are between min and max.
+///
+/// ```
+/// let input = "\
+///     19, 13, 30 @ -2,  1, -2
+///     18, 19, 22 @ -1, -1, -2
+///     20, 25, 34 @ -2, -2, -4
+///     12, 31, 28 @ -1, -2, -1
+///     20, 19, 15 @  1, -5, -3";
+/// assert_eq!(advent_of_code_2023::day24::part1_with_area(input, 7, 27), 2);
+/// ```
+pub fn part1_with_area(input: &str, min: i128, max: i128) -> I {
+    count_collisions_in_area(parse(input), Vec2L(min, min), Vec2L(max, max))
 }
 
 
 pub fn part2(input: &str) -> I  {
     let rays = parse(input);
     let center_position = Vec3L::new(
-        rays.iter().map(|ray| ray.0.x).sum::<i128>() / rays.len() as i128,
-        rays.iter().map(|ray| ray.0.y).sum::<i128>() / rays.len() as i128,
-        rays.iter().map(|ray| ray.0.z).sum::<i128>() / rays.len() as i128,
+        rays.iter().map(|ray| ray.pos.x).sum::<i128>() / rays.len() as i128,
+        rays.iter().map(|ray| ray.pos.y).sum::<i128>() / rays.len() as i128,
+        rays.iter().map(|ray| ray.pos.z).sum::<i128>() / rays.len() as i128,
     );
 
-    let rays = rays.iter().map(|ray| (ray.0 - center_position, ray.1)).vec();
+    let rays = rays.iter().map(|ray| Ray { pos: ray.pos - center_position, vel: ray.vel }).vec();
 
     let rock_start = solve_rock_trajectory(rays) + center_position;
     (rock_start.x + rock_start.y + rock_start.z) as I
@@ -215,6 +281,15 @@ mod tests {
         assert_eq!(count_collisions_in_area(parse(input), Vec2L(7, 7), Vec2L(27, 27)), 2);
     }
 
+    #[test]
+    fn test_ray_at() {
+        let ray = parse_line("19, 13, 30 @ -2,  1, -2");
+        assert_eq!(ray, Ray { pos: Vec3L::new(19, 13, 30), vel: Vec3L::new(-2, 1, -2) });
+        assert_eq!(ray.at(0), Vec3L::new(19, 13, 30));
+        assert_eq!(ray.at(5), Vec3L::new(9, 18, 20));
+        assert_eq!(ray.at(-1), Vec3L::new(21, 12, 32));
+    }
+
     #[test]
     fn test_intersection_points() {
         {
@@ -239,6 +314,50 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_intersection_on_area_edge() {
+        // The rays intersect at (27, 10), exactly on the edge of the test area
+        let a = (Vec2L(27, 0), Vec2L(0, 1));
+        let b = (Vec2L(20, 10), Vec2L(1, 0));
+        let (p, den) = ray_intersection_2d_exact(a, b).unwrap();
+        assert_eq!((p / den, p.x() % den, p.y() % den), (Vec2L(27, 10), 0, 0));
+
+        let input = "\
+        27, 0, 0 @ 0, 1, 0
+        20, 10, 0 @ 1, 0, 0";
+        assert_eq!(count_collisions_in_area(parse(input), Vec2L(7, 7), Vec2L(27, 27)), 1);
+        assert_eq!(count_collisions_in_area(parse(input), Vec2L(7, 7), Vec2L(26, 27)), 0);
+
+        // Intersections in the past don't count
+        let a = (Vec2L(27, 20), Vec2L(0, 1));
+        assert_eq!(ray_intersection_2d_exact(a, b), None);
+    }
+
+    #[test]
+    fn test_non_integer_intersection_near_area_edge() {
+        // Intersects at (27.4, 10), which would be rounded to (27, 10), inside the area
+        let a = (Vec2L(0, 0), Vec2L(137, 50));
+        let b = (Vec2L(0, 10), Vec2L(1, 0));
+        let p = ray_intersection_2d_exact(a, b).unwrap();
+        assert_eq!(ray_intersection_2d(a, b), Some(Vec2L(27, 10)));
+        assert!(!is_in_area(p, Vec2L(7, 7), Vec2L(27, 27)));
+        assert!(is_in_area(p, Vec2L(7, 7), Vec2L(28, 27)));
+
+        // Intersects at (6.6, 10), which would be rounded to (7, 10), inside the area
+        let a = (Vec2L(0, 0), Vec2L(33, 50));
+        let p = ray_intersection_2d_exact(a, b).unwrap();
+        assert_eq!(ray_intersection_2d(a, b), Some(Vec2L(7, 10)));
+        assert!(!is_in_area(p, Vec2L(7, 7), Vec2L(27, 27)));
+        assert!(is_in_area(p, Vec2L(6, 7), Vec2L(27, 27)));
+
+        let input = "\
+        0, 0, 0 @ 137, 50, 0
+        0, 10, 0 @ 1, 0, 0
+        0, 0, 0 @ 33, 50, 0
+        0, 0, 0 @ 133, 50, 0";
+        assert_eq!(count_collisions_in_area(parse(input), Vec2L(7, 7), Vec2L(27, 27)), 1);
+    }
+
     #[test]
     fn test_part2() {
         let input = "\
@@ -13,46 +13,75 @@
 /// and I stop as soon as one of the random runs finds the solution.
 /// It usually takes less than a second now.
 /// 
+/// Since Karger is not guaranteed to find the cut, there is also a
+/// deterministic alternative using the Stoer-Wagner algorithm
+/// (see part1_deterministic).
+/// 
 /// There is no part 2 today, as this is the last puzzle, and part 2 consists
-/// of clicking a button on the Advent of Code website.
+/// of clicking a button on the Advent of Code website. Instead, part2 is a
+/// self-check of part 1: It removes the found cut edges from the graph and
+/// counts the component sizes again. The cut is shared between the parts (see
+/// Day25), so Karger only runs once when running both parts.
 
-use rand::Rng;
+use std::collections::BinaryHeap;
 
-use crate::utils::*;
+use crate::{utils::*, solution_import::SolutionWithContext};
 use rayon::prelude::*;
 
-/// A node is identified by three chars (like "abc").
-type Node = (char, char, char);
+/// A node is identified by a numeric id (see NodeIds).
+type Node = u32;
 
 /// A graph is a map from nodes to their adjacent nodes
 type Graph = HashMap<Node, Vec<Node>>;
 
-/// Parses a node name
-fn parse_node_id(name: &str) -> Node {
-    (name.to_owned() + "__").chars()
-        .next_tuple()
-        .unwrap()
+/// Assigns a numeric id to each node name, so that node names of any length
+/// can be used, while keeping the graph cheap to copy and hash.
+#[derive(Default, Debug)]
+struct NodeIds {
+    ids: HashMap<String, Node>,
+    names: Vec<String>,
 }
 
-/// Parses the input graph
-fn parse(input: &str) -> Graph {
+impl NodeIds {
+    /// Returns the id for the given node name, assigning a new id if needed
+    fn id(&mut self, name: &str) -> Node {
+        if let Some(&id) = self.ids.get(name) {
+            id
+        } else {
+            let id = self.names.len() as Node;
+            self.ids.insert(name.to_owned(), id);
+            self.names.push(name.to_owned());
+            id
+        }
+    }
+
+    /// Returns the node name for the given id
+    fn name(&self, id: Node) -> &str {
+        &self.names[id as usize]
+    }
+}
+
+/// Parses the input graph, and returns the node names along with it
+fn parse_with_names(input: &str) -> (Graph, NodeIds) {
     let items = input.lines().map(parse::alphanums).vec();
     let mut graph = HashMap::new();
+    let mut node_ids = NodeIds::default();
 
     // Each line contains one node and **some** of its adjacent nodes
     // (adjacent nodes are not listed if they already list this node as adjacent)
     for item in items.iter() {
-        let node_a = &item[0];
+        let node_a = node_ids.id(&item[0]);
         // Connections are not listed for both directions, so add the edge to
         // the other node
-        for node_b in item[1..].iter() {
-            graph.entry(parse_node_id(node_a))
+        for name_b in item[1..].iter() {
+            let node_b = node_ids.id(name_b);
+            graph.entry(node_a)
                  .or_insert(vec![])
-                 .push(parse_node_id(node_b));
+                 .push(node_b);
             
-            graph.entry(parse_node_id(node_b))
+            graph.entry(node_b)
                  .or_insert(vec![])
-                 .push(parse_node_id(node_a));
+                 .push(node_a);
         }
     }
 
@@ -61,84 +90,206 @@ fn parse(input: &str) -> Graph {
         *adjacent = adjacent.iter().unique().cloned().vec();
     }
 
-    graph
+    (graph, node_ids)
+}
+
+/// Parses the input graph
+fn parse(input: &str) -> Graph {
+    parse_with_names(input).0
 }
 
 /// Finds a min cut candidate.
 /// Uses Karger's algorithm for finding the minimum cut in a graph.
-/// Returns the number of edges in the cut, and the two subgraphs sizes
-pub fn karger_min_cut(graph: &Graph) -> (I, I, I) {
+/// Returns the edges in the cut, and the two subgraphs sizes
+pub fn karger_min_cut_edges(graph: &Graph) -> (Vec<(Node, Node)>, I, I) {
     let mut rng = rand::thread_rng();
 
-    // For each node, in addition to the adjacency list, we keep a list of nodes
-    // that have been merged into this node. Initially, the list contains the
-    // node itself.
-    let mut merged_graph = HashMap::<Node, (Vec<Node>, Vec<Node>)>::from_iter(
-        graph.iter().map(|(node, adjacent)| (
-            node.to_owned(),
-            (adjacent.clone(), vec![node.to_owned()])
-        ))
-    );
-
-    while merged_graph.len() > 2 {
-        // Choose a node at random, to be merged into another node
-        let merged_node = merged_graph.keys().nth(rng.gen_range(0..merged_graph.len())).unwrap().clone();
-        // Remove it from the graph
-        let (old_neighbors, previously_merged) = merged_graph.remove(&merged_node).unwrap();
-        // Choose a neighbor at random
-        let merge_into = old_neighbors[rng.gen_range(0..old_neighbors.len())].clone();
-
-        // Merge the node into the neighbor
-        let (mut new_merged_neighbors, mut new_merged_inner) = merged_graph.remove(&merge_into).unwrap();
-        assert!(!new_merged_neighbors.contains(&merge_into));
-
-        new_merged_inner.extend(previously_merged.into_iter());
-        new_merged_neighbors.extend(old_neighbors.into_iter());
-        new_merged_neighbors = new_merged_neighbors.into_iter().filter(|n| n != &merge_into && n != &merged_node).vec();
-        assert!(!new_merged_neighbors.contains(&merge_into));
-        merged_graph.insert(merge_into.clone(), (new_merged_neighbors, new_merged_inner));
-
-        // Update all neighbors of the merged node to point to the neighbor instead
-        for (_, (neighbors, _)) in merged_graph.iter_mut() {
-            if neighbors.contains(&merged_node) {
-                *neighbors = neighbors
-                    .iter()
-                    .map(|neighbor| if *neighbor == merged_node { &merge_into } else { neighbor })
-                    .unique()
-                    .cloned()
-                    .vec();
-            }
-        }
+    // Contract random edges until only two supernodes are left
+    let mut contractible = graph::ContractibleGraph::new(graph);
+    while contractible.len() > 2 {
+        contractible.contract_random_edge(&mut rng);
     }
 
-    // Get the two remaining nodes and reconstruct the subgraph information from
-    // the "merged" lists in them
-    let (node_a, node_b) = merged_graph.values().collect_tuple().unwrap();
-    let (_, merged_a) = node_a;
-    let (_, merged_b) = node_b;
+    // The two remaining supernodes are the two subgraphs
+    let (merged_a, merged_b) = contractible.components().into_iter().collect_tuple().unwrap();
 
     // The remaining edges are the ones that connect the two subgraphs
-    let cut_edges = merged_a.iter().map(|node| {
-        graph[node].iter()
+    let cut_edges = merged_a.iter().map(|&node| {
+        graph[&node].iter()
             .filter(|&old_neighbor| merged_b.contains(old_neighbor))
-    }).flatten().count() as I;
+            .map(move |&old_neighbor| (node, old_neighbor))
+    }).flatten().vec();
 
     (cut_edges, merged_a.len() as I, merged_b.len() as I)
 }
 
+/// Finds a min cut candidate using Karger's algorithm.
+/// Returns the number of edges in the cut, and the two subgraphs sizes
+pub fn karger_min_cut(graph: &Graph) -> (I, I, I) {
+    let (cut_edges, size_a, size_b) = karger_min_cut_edges(graph);
+    (cut_edges.len() as I, size_a, size_b)
+}
+
+/// Finds the global minimum cut deterministically, using the Stoer-Wagner
+/// algorithm. Returns the number of edges in the cut, and the two subgraph sizes
+pub fn stoer_wagner_min_cut(graph: &Graph) -> (I, I, I) {
+    // Work on node indices instead of node names, and keep edge weights for
+    // merged nodes (merging two nodes adds up the weights of their edges)
+    let nodes = graph.keys().copied().vec();
+    let node_indices = HashMap::<Node, usize>::from_iter(nodes.iter().enumerate().map(|(i, &node)| (node, i)));
+    let mut weights = nodes.iter().map(|node| {
+        HashMap::<usize, I>::from_iter(graph[node].iter().map(|neighbor| (node_indices[neighbor], 1)))
+    }).vec();
+
+    // Number of original nodes that have been merged into each node
+    let mut merged_sizes = vec![1 as I; nodes.len()];
+    let mut active = (0..nodes.len()).vec();
+
+    let mut best_cut = (I::MAX, 0);
+
+    while active.len() > 1 {
+        // Minimum cut phase: starting with a single node, repeatedly add the
+        // node that is most tightly connected to the already added nodes.
+        let mut added = vec![false; nodes.len()];
+        let mut connectivity = vec![0 as I; nodes.len()];
+        let mut queue = BinaryHeap::from([(0 as I, active[0])]);
+        let (mut previous, mut last) = (active[0], active[0]);
+        let mut last_connectivity = 0;
+
+        while let Some((node_connectivity, node)) = queue.pop() {
+            // Skip outdated queue entries
+            if added[node] || node_connectivity != connectivity[node] {
+                continue;
+            }
+            added[node] = true;
+            (previous, last, last_connectivity) = (last, node, node_connectivity);
+
+            for (&neighbor, &weight) in &weights[node] {
+                if !added[neighbor] {
+                    connectivity[neighbor] += weight;
+                    queue.push((connectivity[neighbor], neighbor));
+                }
+            }
+        }
+
+        // The cut between the last added node and all other nodes is a cut
+        // candidate
+        if last_connectivity < best_cut.0 {
+            best_cut = (last_connectivity, merged_sizes[last]);
+        }
+
+        // Merge the last node into the previous one
+        let last_weights = std::mem::take(&mut weights[last]);
+        for (neighbor, weight) in last_weights {
+            weights[neighbor].remove(&last);
+            if neighbor != previous {
+                *weights[previous].entry(neighbor).or_insert(0) += weight;
+                *weights[neighbor].entry(previous).or_insert(0) += weight;
+            }
+        }
+        merged_sizes[previous] += merged_sizes[last];
+        active.retain(|&node| node != last);
+    }
+
+    let (cut_edges, size_a) = best_cut;
+    (cut_edges, size_a, nodes.len() as I - size_a)
+}
+
+/// Part 1, but using the deterministic Stoer-Wagner algorithm instead of
+/// Karger.
+pub fn part1_deterministic(input: &str) -> I {
+    let graph = parse(input);
+    let (_, subgraph_a, subgraph_b) = stoer_wagner_min_cut(&graph);
+    subgraph_a * subgraph_b
+}
+
+/// Runs Karger in parallel until a cut with three edges is found.
+/// Returns the edges in the cut, and the two subgraphs sizes
+fn find_three_edge_cut(graph: &Graph) -> (Vec<(Node, Node)>, I, I) {
+    (0..1000).par_bridge()
+        .map(|_| karger_min_cut_edges(graph))
+        .find_any(|(cut_edges, _, _)| cut_edges.len() == 3)
+        .expect("No cut with three edges found")
+}
+
+/// Returns the sizes of the connected components of the graph, sorted ascending
+fn component_sizes(graph: &Graph) -> Vec<I> {
+    let nodes = graph.keys().copied().vec();
+    graph::connected_components(&nodes, |node| graph[node].clone())
+        .iter()
+        .map(|component| component.len() as I)
+        .sorted()
+        .vec()
+}
+
+/// Removes the given edges (in both directions) from the graph
+fn remove_edges(graph: &Graph, edges: &[(Node, Node)]) -> Graph {
+    graph.iter().map(|(&node, adjacent)| {
+        let adjacent = adjacent.iter()
+            .filter(|&&other| !edges.contains(&(node, other)) && !edges.contains(&(other, node)))
+            .copied()
+            .vec();
+        (node, adjacent)
+    }).collect()
+}
+
+/// Both parts use the same three edge cut, so it is computed once and shared
+/// between the parts. This way, part 2 checks the cut that part 1 used.
+pub struct Day25;
+
+/// The graph, and the three edge cut found by Karger, with the two subgraph sizes
+pub struct Cut {
+    graph: Graph,
+    node_ids: NodeIds,
+    edges: Vec<(Node, Node)>,
+    sizes: (I, I),
+}
+
+impl SolutionWithContext for Day25 {
+    type Context = Cut;
+
+    fn context(input: &str) -> Cut {
+        let (graph, node_ids) = parse_with_names(input);
+        let (edges, subgraph_a, subgraph_b) = find_three_edge_cut(&graph);
+        Cut { graph, node_ids, edges, sizes: (subgraph_a, subgraph_b) }
+    }
+
+    /// Part 1: Find the three edges that, when removed, split the graph into two
+    /// separate graphs. Return the product of the sizes of the two subgraphs.
+    fn part1(cut: &Cut) -> I {
+        if verbose() {
+            for (a, b) in &cut.edges {
+                println!("Cut edge: {} - {}", cut.node_ids.name(*a), cut.node_ids.name(*b));
+            }
+        }
+
+        cut.sizes.0 * cut.sizes.1
+    }
+
+    /// No part 2 today :)
+    /// Instead, this verifies the part 1 result: It removes the cut edges from the
+    /// graph and checks that exactly two components with the expected sizes remain.
+    /// Returns the same result as part 1.
+    fn part2(cut: &Cut) -> I {
+        let sizes = component_sizes(&remove_edges(&cut.graph, &cut.edges));
+        let mut expected = vec![cut.sizes.0, cut.sizes.1];
+        expected.sort();
+        assert_eq!(sizes, expected, "Removing the cut edges does not result in the expected components");
+
+        sizes[0] * sizes[1]
+    }
+}
+
 /// Part 1: Find the three edges that, when removed, split the graph into two
 /// separate graphs. Return the product of the sizes of the two subgraphs.
 pub fn part1(input: &str) -> I {
-    let graph = parse(input);
-    (0..1000).par_bridge().map(|_| {
-        let (cuts, subgraph_a, subgraph_b) = karger_min_cut(&graph);
-        (cuts, (subgraph_a * subgraph_b) as I)
-    }).find_any(|(cuts, _)| *cuts == 3).expect("No cut with three edges found").1
+    Day25::part1(&Day25::context(input))
 }
 
 /// No part 2 today :)
+/// Instead, this verifies the part 1 result (see Day25::part2).
 pub fn part2(input: &str) -> I {
-    0
+    Day25::part2(&Day25::context(input))
 }
 
 #[cfg(test)]
@@ -163,6 +314,79 @@ mod tests {
             frs: qnr lhk lsr";
 
         assert_eq!(part1(input), 54);
+        assert_eq!(part2(input), 54);
+        assert_eq!(part1_deterministic(input), 54);
+        assert_eq!(stoer_wagner_min_cut(&parse(input)).0, 3);
+    }
+
+    #[test]
+    fn test_shared_cut() {
+        let input = "\
+            jqt: rhn xhk nvd
+            rsh: frs pzl lsr
+            xhk: hfx
+            cmg: qnr nvd lhk bvb
+            rhn: xhk bvb hfx
+            bvb: xhk hfx
+            pzl: lsr hfx nvd
+            qnr: nvd
+            ntq: jqt hfx bvb xhk
+            nvd: lhk
+            lsr: lhk
+            rzs: qnr cmg lsr rsh
+            frs: qnr lhk lsr";
+
+        assert_eq!(solution_import::run_with_context::<Day25>(input), (54, 54));
+
+        // Part 2 checks the cut of the context, so a wrong cut is detected
+        let mut cut = Day25::context(input);
+        cut.edges.pop();
+        assert!(std::panic::catch_unwind(|| Day25::part2(&cut)).is_err());
+    }
+
+    #[test]
+    fn test_cut_edges() {
+        let input = "\
+            jqt: rhn xhk nvd
+            rsh: frs pzl lsr
+            xhk: hfx
+            cmg: qnr nvd lhk bvb
+            rhn: xhk bvb hfx
+            bvb: xhk hfx
+            pzl: lsr hfx nvd
+            qnr: nvd
+            ntq: jqt hfx bvb xhk
+            nvd: lhk
+            lsr: lhk
+            rzs: qnr cmg lsr rsh
+            frs: qnr lhk lsr";
+
+        let mut graph = parse(input);
+        let (cut_edges, _, _) = (0..1000)
+            .map(|_| karger_min_cut_edges(&graph))
+            .find(|(cut_edges, _, _)| cut_edges.len() == 3)
+            .unwrap();
+
+        // Removing the cut edges must split the graph into two components
+        for (a, b) in cut_edges {
+            graph.get_mut(&a).unwrap().retain(|&n| n != b);
+            graph.get_mut(&b).unwrap().retain(|&n| n != a);
+        }
+        assert_eq!(component_sizes(&graph), vec![6, 9]);
+    }
+
+    #[test]
+    fn test_long_node_names() {
+        // These names would collide if only the first three chars were used
+        let input = "\
+            abcd: abce abc
+            abc: abce";
+
+        let (graph, node_ids) = parse_with_names(input);
+        assert_eq!(graph.len(), 3);
+        assert_eq!(node_ids.names, vec!["abcd", "abce", "abc"]);
+        assert_eq!(graph[&node_ids.ids["abcd"]].len(), 2);
+        assert_eq!(graph[&node_ids.ids["abc"]].len(), 2);
     }
 
     #[test]
@@ -179,6 +403,8 @@ mod tests {
             re: ra rb rc rd";
 
         assert_eq!(part1(input), 25);
+        assert_eq!(part1_deterministic(input), 25);
+        assert_eq!(stoer_wagner_min_cut(&parse(input)).0, 3);
     }
 
 }
\ No newline at end of file
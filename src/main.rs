@@ -4,56 +4,57 @@
 // See the README.md for more information, and the dayXX modules for
 // my solutions.
 
-#![feature(iter_array_chunks)]
-#![feature(let_chains)]
-#![feature(iter_map_windows)]
-#![feature(anonymous_lifetime_in_impl_trait)]
-#![feature(slice_flatten)]
-
 use std::env;
 
-mod utils; 
-pub use utils::*;
-use utils::solution_import::*;
-
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
-mod day06;
-mod day07;
-mod day08;
-mod day09;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day19;
-mod day20;
-mod day21;
-mod day22;
-mod day23;
-mod day24;
-mod day25;
+use advent_of_code_2023::solution_import::*;
 
 fn main() {
-    // Load the solutions
+    // Load the solutions, making sure that none are missing or duplicated
     let solutions = solutions();
-    let &latest_day = solutions.iter().map(|(day, _, _)| day).max().unwrap();
+    validate_solutions(&solutions).expect("Invalid solutions");
+    let mut solutions = solutions_map(solutions);
+    let latest_day = *solutions.keys().max().unwrap();
+
+    // Parse the command line arguments to get the selected day (or use the latest day),
+    // and the output format
+    let args: Vec<String> = env::args().skip(1).collect();
+    let format = if args.iter().any(|arg| arg == "--json") { OutputFormat::Json } else { OutputFormat::Human };
+    let budget = budget_from_env();
+
+    // List the days, and whether inputs and examples exist
+    if args.iter().any(|arg| arg == "--list") {
+        println!("{}", format_day_list(&day_statuses(&solutions.into_values().collect::<Vec<_>>())));
+        return;
+    }
+
+    // Run all days in parallel, skipping days without input file
+    if args.iter().any(|arg| arg == "--all") {
+        for result in run_all(&solutions.into_values().collect::<Vec<_>>()) {
+            print_day_result(&result, format, budget);
+        }
+        return;
+    }
+
+    let selected_day = args.iter()
+        .find(|arg| !arg.starts_with("--"))
+        .and_then(|s| s.parse::<u32>().ok())
+        .unwrap_or(latest_day);
 
-    // Parse the command line argument to get the selected day, or use the latest day
-    let args: Vec<String> = env::args().collect();
-    let selected_day = args.get(1).and_then(|s| s.parse::<u32>().ok()).unwrap_or(latest_day);
+    // Print the parsed input of the selected day instead of solving it
+    if args.iter().any(|arg| arg == "--dump-parsed") {
+        #[cfg(feature = "dump-parsed")]
+        {
+            let dump = dump_parsed(selected_day, &load_input(selected_day));
+            println!("{}", dump.unwrap_or_else(|| panic!("No parsed structure dump for day {}", selected_day)));
+            return;
+        }
+        #[cfg(not(feature = "dump-parsed"))]
+        panic!("--dump-parsed requires the dump-parsed feature (cargo run --features dump-parsed)");
+    }
 
     // Get the solution for the selected day
-    let solution = solutions.into_iter().find(|(d, _, _)| *d == selected_day).unwrap();
+    let solution = solutions.remove(&selected_day).expect("No solution for the selected day");
     
-    // Run the solution
-    run_solution_day(solution);
+    // Run the solution, optionally highlighting parts that take longer than AOC_BUDGET_MS
+    run_solution_day(solution, format, budget);
 }
\ No newline at end of file
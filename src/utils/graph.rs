@@ -0,0 +1,175 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use itertools::Itertools;
+use rand::Rng;
+
+use super::IterHelpers;
+
+/// Undirected graph that supports contracting edges, as used by Karger's min
+/// cut algorithm. Each node of the contracted graph is a supernode that keeps
+/// track of the original nodes that have been merged into it.
+#[derive(Clone, Debug)]
+pub struct ContractibleGraph<N> {
+    /// For each supernode, the adjacent supernodes and the merged original nodes
+    nodes: HashMap<N, (Vec<N>, Vec<N>)>,
+}
+
+impl<N: Copy + Eq + Hash> ContractibleGraph<N> {
+    /// Creates the graph from adjacency lists. Every node is its own supernode.
+    pub fn new(graph: &HashMap<N, Vec<N>>) -> Self {
+        let nodes = graph.iter()
+            .map(|(&node, adjacent)| (node, (adjacent.clone(), vec![node])))
+            .collect();
+        ContractibleGraph { nodes }
+    }
+
+    /// Number of remaining supernodes
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Contracts a random edge: Chooses a supernode and one of its neighbors at
+    /// random, and merges the supernode into that neighbor.
+    pub fn contract_random_edge(&mut self, rng: &mut impl Rng) {
+        // Choose a node at random, to be merged into another node
+        let merged_node = *self.nodes.keys().nth(rng.gen_range(0..self.nodes.len())).unwrap();
+        // Remove it from the graph
+        let (old_neighbors, previously_merged) = self.nodes.remove(&merged_node).unwrap();
+        // Choose a neighbor at random
+        let merge_into = old_neighbors[rng.gen_range(0..old_neighbors.len())];
+
+        // Merge the node into the neighbor
+        let (neighbors, inner) = self.nodes.get_mut(&merge_into).unwrap();
+        inner.extend(previously_merged);
+        neighbors.extend(old_neighbors);
+        *neighbors = neighbors.iter()
+            .filter(|&&n| n != merge_into && n != merged_node)
+            .unique()
+            .copied()
+            .vec();
+
+        // Update all neighbors of the merged node to point to the neighbor instead
+        for (neighbors, _) in self.nodes.values_mut() {
+            if neighbors.contains(&merged_node) {
+                *neighbors = neighbors.iter()
+                    .map(|&neighbor| if neighbor == merged_node { merge_into } else { neighbor })
+                    .unique()
+                    .vec();
+            }
+        }
+    }
+
+    /// Returns the original nodes that have been merged into each supernode
+    pub fn components(&self) -> Vec<&Vec<N>> {
+        self.nodes.values().map(|(_, inner)| inner).vec()
+    }
+
+    /// Returns the number of original nodes in each supernode, sorted ascending
+    pub fn component_sizes(&self) -> Vec<usize> {
+        self.nodes.values().map(|(_, inner)| inner.len()).sorted().vec()
+    }
+}
+
+/// Finds the connected components of a graph, given its nodes and a function
+/// that returns the neighbors of a node. Works for adjacency maps as well as
+/// grids. The components are returned in the order of their first node in
+/// `nodes`.
+pub fn connected_components<N: Clone + Eq + Hash>(nodes: &[N], neighbors: impl Fn(&N) -> Vec<N>) -> Vec<Vec<N>> {
+    let mut visited = HashSet::<N>::new();
+    let mut components = vec![];
+    for start in nodes {
+        if !visited.insert(start.clone()) {
+            continue;
+        }
+
+        // Depth-first search from the start node
+        let mut component = vec![];
+        let mut stack = vec![start.clone()];
+        while let Some(node) = stack.pop() {
+            stack.extend(neighbors(&node).into_iter().filter(|next| visited.insert(next.clone())));
+            component.push(node);
+        }
+        components.push(component);
+    }
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contract_to_two_supernodes() {
+        // Two triangles, connected by a single edge
+        let graph = HashMap::from([
+            (1, vec![2, 3]),
+            (2, vec![1, 3]),
+            (3, vec![1, 2, 4]),
+            (4, vec![3, 5, 6]),
+            (5, vec![4, 6]),
+            (6, vec![4, 5]),
+        ]);
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let mut contractible = ContractibleGraph::new(&graph);
+            assert_eq!(contractible.len(), 6);
+            assert_eq!(contractible.component_sizes(), vec![1; 6]);
+
+            while contractible.len() > 2 {
+                contractible.contract_random_edge(&mut rng);
+            }
+
+            let sizes = contractible.component_sizes();
+            assert_eq!(sizes.len(), 2);
+            assert_eq!(sizes.iter().sum::<usize>(), 6);
+            assert!(contractible.components().iter().flat_map(|c| c.iter()).sorted().eq([1, 2, 3, 4, 5, 6].iter()));
+        }
+    }
+
+    #[test]
+    fn test_connected_components() {
+        // Two components: 1-2-3 and 4-5
+        let graph = HashMap::from([
+            (1, vec![2]),
+            (2, vec![1, 3]),
+            (3, vec![2]),
+            (4, vec![5]),
+            (5, vec![4]),
+        ]);
+        let components = connected_components(&[1, 2, 3, 4, 5], |node| graph[node].clone());
+        let components = components.into_iter().map(|c| c.into_iter().sorted().vec()).vec();
+        assert_eq!(components, vec![vec![1, 2, 3], vec![4, 5]]);
+
+        // Fully connected
+        let nodes = (0..5).vec();
+        let components = connected_components(&nodes, |&node| nodes.iter().copied().filter(|&n| n != node).vec());
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0].len(), 5);
+    }
+
+    #[test]
+    fn test_connected_components_grid() {
+        // Cells of a grid, with walls in between
+        let grid = [
+            "..#.",
+            "..#.",
+            "###.",
+        ];
+        let cells = (0..3i64).cartesian_product(0..4i64)
+            .filter(|&(y, x)| grid[y as usize].as_bytes()[x as usize] == b'.')
+            .vec();
+        let neighbors = |&(y, x): &(i64, i64)| {
+            [(y - 1, x), (y + 1, x), (y, x - 1), (y, x + 1)].into_iter()
+                .filter(|cell| cells.contains(cell))
+                .vec()
+        };
+        let sizes = connected_components(&cells, neighbors).iter().map(|c| c.len()).vec();
+        assert_eq!(sizes, vec![4, 3]);
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::HashSet;
+use crate::vec2::Vec2;
+use crate::utils::I;
+
+/// A sparse 2D grid, stored as the set of positions of all "set" tiles.
+/// The grid has a base tile of size w*h, which can either be used on its own
+/// (base_contains), or be repeated infinitely in all directions (is_set).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SparseGrid {
+    pub tiles: HashSet<Vec2>,
+    pub w: I,
+    pub h: I,
+}
+
+impl SparseGrid {
+    pub fn new(tiles: HashSet<Vec2>, w: I, h: I) -> Self {
+        SparseGrid { tiles, w, h }
+    }
+
+    /// Maps any position to the corresponding position in the base tile
+    pub fn wrap_pos(&self, pos: Vec2) -> Vec2 {
+        Vec2(pos.x().rem_euclid(self.w), pos.y().rem_euclid(self.h))
+    }
+
+    /// Checks if the tile is set, treating the grid as infinitely repeating
+    pub fn is_set(&self, pos: Vec2) -> bool {
+        self.tiles.contains(&self.wrap_pos(pos))
+    }
+
+    /// Checks if the tile is set in the base tile (without repeating)
+    pub fn base_contains(&self, pos: Vec2) -> bool {
+        self.tiles.contains(&pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_negative() {
+        let grid = SparseGrid::new(HashSet::from([Vec2(0, 0), Vec2(2, 1)]), 3, 2);
+
+        assert_eq!(grid.wrap_pos(Vec2(-1, 0)), Vec2(2, 0));
+        assert_eq!(grid.wrap_pos(Vec2(0, -1)), Vec2(0, 1));
+        assert_eq!(grid.wrap_pos(Vec2(-4, -3)), Vec2(2, 1));
+        assert_eq!(grid.wrap_pos(Vec2(-3, -2)), Vec2(0, 0));
+
+        assert!(grid.is_set(Vec2(-1, -1)));
+        assert!(grid.is_set(Vec2(-3, -4)));
+        assert!(grid.is_set(Vec2(5, 3)));
+        assert!(!grid.is_set(Vec2(-2, -1)));
+    }
+
+    #[test]
+    fn test_base_contains() {
+        let grid = SparseGrid::new(HashSet::from([Vec2(0, 0), Vec2(2, 1)]), 3, 2);
+
+        assert!(grid.base_contains(Vec2(2, 1)));
+        assert!(!grid.base_contains(Vec2(-1, -1)));
+        assert!(!grid.base_contains(Vec2(3, 2)));
+    }
+}
@@ -0,0 +1,86 @@
+use itertools::Itertools;
+
+use crate::vec3::Vec3;
+use crate::vec3_128::Vec3L;
+
+/// Formats a value as JSON, for dumping parsed puzzle structures (see
+/// dump_parsed in solution_import). Like format_json and parse_answers, this
+/// is written by hand, so that we don't need a JSON library.
+pub trait ToJson {
+    fn to_json(&self) -> String;
+}
+
+macro_rules! number_to_json {
+    ($($t:ty),*) => {
+        $(impl ToJson for $t {
+            fn to_json(&self) -> String {
+                self.to_string()
+            }
+        })*
+    };
+}
+
+number_to_json!(i64, i128, usize, u32);
+
+impl ToJson for str {
+    fn to_json(&self) -> String {
+        let escaped = self.chars().map(|c| match c {
+            '"' => "\\\"".to_string(),
+            '\\' => "\\\\".to_string(),
+            '\n' => "\\n".to_string(),
+            c if c.is_control() => format!("\\u{:04x}", c as u32),
+            c => c.to_string(),
+        }).join("");
+        format!("\"{}\"", escaped)
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> String {
+        self.as_str().to_json()
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn to_json(&self) -> String {
+        format!("[{}]", self.iter().map(|item| item.to_json()).join(","))
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> String {
+        self.as_slice().to_json()
+    }
+}
+
+impl ToJson for Vec3 {
+    fn to_json(&self) -> String {
+        json_object(&[("x", self.x.to_json()), ("y", self.y.to_json()), ("z", self.z.to_json())])
+    }
+}
+
+impl ToJson for Vec3L {
+    fn to_json(&self) -> String {
+        json_object(&[("x", self.x.to_json()), ("y", self.y.to_json()), ("z", self.z.to_json())])
+    }
+}
+
+/// Formats a JSON object from the field names and their JSON values
+pub fn json_object(fields: &[(&str, String)]) -> String {
+    format!("{{{}}}", fields.iter().map(|(name, value)| format!("{}:{}", name.to_json(), value)).join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(42i64.to_json(), "42");
+        assert_eq!("a\"b\\c\n".to_json(), r#""a\"b\\c\n""#);
+        assert_eq!(vec![1i64, 2, 3].to_json(), "[1,2,3]");
+        assert_eq!(Vec::<i64>::new().to_json(), "[]");
+        assert_eq!(Vec3::new(1, -2, 3).to_json(), r#"{"x":1,"y":-2,"z":3}"#);
+        assert_eq!(json_object(&[]), "{}");
+    }
+}
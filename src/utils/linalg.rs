@@ -0,0 +1,74 @@
+use num::rational::Ratio;
+
+/// Solves the linear system `matrix * x = rhs` exactly. Uses fraction-free
+/// (Bareiss) Gaussian elimination, so that all intermediate values stay
+/// integers, and only the back substitution needs fractions.
+/// Returns None if the matrix is singular.
+pub fn solve_rational(matrix: Vec<Vec<i128>>, rhs: Vec<i128>) -> Option<Vec<Ratio<i128>>> {
+    let n = rhs.len();
+    assert_eq!(matrix.len(), n, "Matrix must have one row per right-hand side value");
+    assert!(matrix.iter().all(|row| row.len() == n), "Matrix must be square");
+
+    // Augmented matrix [matrix | rhs]
+    let mut m: Vec<Vec<i128>> = matrix.into_iter().zip(rhs)
+        .map(|(mut row, value)| { row.push(value); row })
+        .collect();
+
+    let mut previous_pivot = 1;
+    for k in 0..n {
+        // Find a row with a non-zero pivot, and swap it into place
+        let pivot_row = (k..n).find(|&i| m[i][k] != 0)?;
+        m.swap(k, pivot_row);
+
+        // Eliminate the column below the pivot. The division by the previous
+        // pivot is always exact.
+        for i in (k + 1)..n {
+            for j in (k + 1)..=n {
+                m[i][j] = (m[k][k] * m[i][j] - m[i][k] * m[k][j]) / previous_pivot;
+            }
+            m[i][k] = 0;
+        }
+        previous_pivot = m[k][k];
+    }
+
+    // Back substitution
+    let mut solution = vec![Ratio::from_integer(0); n];
+    for i in (0..n).rev() {
+        let sum = ((i + 1)..n).map(|j| solution[j] * m[i][j]).sum::<Ratio<i128>>();
+        solution[i] = (Ratio::from_integer(m[i][n]) - sum) / m[i][i];
+    }
+    Some(solution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve_2x2() {
+        // Needs a row swap, since the first pivot is zero
+        let solution = solve_rational(vec![vec![0, 2], vec![3, 1]], vec![1, 1]).unwrap();
+        assert_eq!(solution, vec![Ratio::new(1, 6), Ratio::new(1, 2)]);
+    }
+
+    #[test]
+    fn test_solve_3x3() {
+        let matrix = vec![
+            vec![3, 2, -1],
+            vec![2, -2, 4],
+            vec![-1, 1, 2],
+        ];
+        let solution = solve_rational(matrix, vec![1, -2, 0]).unwrap();
+        assert_eq!(solution, vec![Ratio::new(-1, 20), Ratio::new(9, 20), Ratio::new(-1, 4)]);
+    }
+
+    #[test]
+    fn test_solve_singular() {
+        let matrix = vec![
+            vec![1, 2, 3],
+            vec![2, 4, 6],
+            vec![1, 0, 1],
+        ];
+        assert_eq!(solve_rational(matrix, vec![1, 2, 3]), None);
+    }
+}
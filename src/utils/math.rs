@@ -0,0 +1,61 @@
+/// Combines congruences T = r (mod m) into a single one, or returns None if
+/// they are inconsistent. The moduli do not need to be coprime.
+/// Returns an error if the combined modulus does not fit into an i64.
+pub fn crt(residues: &[(i64, i64)]) -> Result<Option<(i64, i64)>, String> {
+    let mut combined = (0, 1);
+    for &(r2, m2) in residues {
+        let (r1, m1) = combined;
+        let (r1, m1, r2, m2) = (r1 as i128, m1 as i128, r2 as i128, m2 as i128);
+        let g = num::integer::gcd(m1, m2);
+        if (r2 - r1) % g != 0 {
+            return Ok(None);
+        }
+        // Solve r1 + m1 * k = r2 (mod m2) for k
+        let inverse = num::Integer::extended_gcd(&(m1 / g), &(m2 / g)).x;
+        let k = ((r2 - r1) / g * inverse).rem_euclid(m2 / g);
+        let m = m1 / g * m2;
+        let r = (r1 + m1 * k).rem_euclid(m);
+        let m = i64::try_from(m).map_err(|_| format!("Combined modulus {} does not fit into an i64", m))?;
+        combined = (r as i64, m);
+    }
+    Ok(Some(combined))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crt_coprime() {
+        // x = 2 (mod 3), x = 3 (mod 5), x = 2 (mod 7)
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Ok(Some((23, 105))));
+        assert_eq!(crt(&[(4, 7)]), Ok(Some((4, 7))));
+        assert_eq!(crt(&[]), Ok(Some((0, 1))));
+    }
+
+    #[test]
+    fn test_crt_not_coprime() {
+        // x = 2 (mod 4), x = 4 (mod 6)
+        assert_eq!(crt(&[(2, 4), (4, 6)]), Ok(Some((10, 12))));
+        // Large moduli, the combined modulus is their lcm
+        assert_eq!(
+            crt(&[(0, 1_000_000_007 * 6), (6, 1_000_000_009 * 6)]),
+            Ok(Some((3_000_000_045_000_000_168, 6_000_000_096_000_000_378)))
+        );
+    }
+
+    #[test]
+    fn test_crt_inconsistent() {
+        // x = 1 (mod 4) and x = 2 (mod 6) can't both hold, since x would have to be odd and even
+        assert_eq!(crt(&[(1, 4), (2, 6)]), Ok(None));
+    }
+
+    #[test]
+    fn test_crt_overflow() {
+        // The lcm just fits into an i64
+        assert_eq!(crt(&[(0, 3_037_000_493), (0, 3_037_000_499)]), Ok(Some((0, 9_223_372_012_704_246_007))));
+        // The lcm is larger than i64::MAX
+        assert!(crt(&[(1, 4_000_000_007), (2, 3_000_000_019)]).is_err());
+        assert!(crt(&[(0, i64::MAX), (0, 2)]).is_err());
+    }
+}
@@ -13,6 +13,13 @@ pub mod vec2;
 pub mod vec2_128;
 pub mod vec3;
 pub mod vec3_128;
+pub mod grid;
+pub mod graph;
+pub mod linalg;
+pub mod math;
+#[cfg(any(test, feature = "dump-parsed"))]
+pub mod json;
+pub mod examples;
 pub mod solution_import;
 
 pub mod parse {
@@ -46,6 +53,41 @@ pub mod parse {
             panic!("failed to parse alphanums")
         }
     }
+
+    /// Parses a hexadecimal number without prefix or sign, e.g. "70c71"
+    pub fn hex(input: &str) -> Result<i64, String> {
+        if input.is_empty() {
+            return Err("Empty hex number".to_string());
+        }
+        if let Some(c) = input.chars().find(|c| !c.is_ascii_hexdigit()) {
+            return Err(format!("Invalid hex digit '{}' in \"{}\"", c, input));
+        }
+        i64::from_str_radix(input, 16).map_err(|e| format!("Invalid hex number \"{}\": {}", input, e))
+    }
+
+    /// Splits the input into blocks of lines that are separated by empty lines.
+    /// Lines are trimmed, and repeated or surrounding empty lines are ignored.
+    pub fn blocks(input: &str) -> Vec<Vec<&str>> {
+        split_blocks(input, true)
+    }
+
+    /// Same as blocks, but keeps the surrounding whitespace of each line
+    pub fn blocks_untrimmed(input: &str) -> Vec<Vec<&str>> {
+        split_blocks(input, false)
+    }
+
+    fn split_blocks(input: &str, trim: bool) -> Vec<Vec<&str>> {
+        let mut blocks = vec![vec![]];
+        for line in input.lines() {
+            if line.trim().is_empty() {
+                blocks.push(vec![]);
+            } else {
+                blocks.last_mut().unwrap().push(if trim { line.trim() } else { line });
+            }
+        }
+        blocks.retain(|block| !block.is_empty());
+        blocks
+    }
 }
 
 use std::{fmt::Debug, str::FromStr};
@@ -53,6 +95,11 @@ use std::{fmt::Debug, str::FromStr};
 pub trait IterHelpers<T> {
     fn vec(self) -> Vec<T>;
     fn pair(self) -> (T, T);
+    /// Iterates over all pairs of consecutive items, e.g. [1, 2, 3] -> (1, 2), (2, 3)
+    fn windows2(self) -> impl Iterator<Item=(T, T)> where T: Clone;
+    /// Iterates over non-overlapping pairs, e.g. [1, 2, 3, 4, 5] -> [1, 2], [3, 4].
+    /// An incomplete last pair is dropped.
+    fn chunks2(self) -> impl Iterator<Item=[T; 2]>;
 }
 
 impl<IterT, T> IterHelpers<T> for IterT
@@ -63,6 +110,12 @@ impl<IterT, T> IterHelpers<T> for IterT
     fn pair(mut self) -> (T, T) {
         (self.next().unwrap(), self.next().unwrap())
     }
+    fn windows2(self) -> impl Iterator<Item=(T, T)> where T: Clone {
+        self.tuple_windows()
+    }
+    fn chunks2(self) -> impl Iterator<Item=[T; 2]> {
+        self.tuples().map(|(a, b)| [a, b])
+    }
 }
 
 pub trait PairHelpers<T> {
@@ -87,6 +140,18 @@ impl<IterT, T, E> UnwrapIterResult<T> for IterT
     }
 }
 
+pub trait TryIterResult<T, E> {
+    /// Collects the results into a vector, returning the first error
+    fn try_vec(self) -> Result<Vec<T>, E>;
+}
+
+impl<IterT, T, E> TryIterResult<T, E> for IterT
+    where IterT: Iterator<Item=Result<T,E>> {
+    fn try_vec(self) -> Result<Vec<T>, E> {
+      self.collect()
+    }
+}
+
 pub trait UnwrapIterOption<T> {
     fn unwrap(self) -> impl Iterator<Item=T>;
 }
@@ -99,14 +164,21 @@ impl<IterT, T> UnwrapIterOption<T> for IterT
 }
 
 pub trait ParseStrIter {
+    /// Parses each string as i64, panicking on invalid numbers
     fn parse_i64(self) -> impl Iterator<Item=i64>;
+    /// Parses each string as i64, with an error containing the offending string
+    fn try_parse_i64(self) -> impl Iterator<Item=Result<i64, String>>;
 }
 
 impl<'a, IterT> ParseStrIter for IterT
     where IterT: Iterator<Item=&'a str>
 {
     fn parse_i64(self) -> impl Iterator<Item=i64> {
-        self.map(|s| s.parse::<i64>().unwrap())
+        self.try_parse_i64().map(|r| r.unwrap())
+    }
+
+    fn try_parse_i64(self) -> impl Iterator<Item=Result<i64, String>> {
+        self.map(|s| s.parse::<i64>().map_err(|e| format!("Invalid number \"{}\": {}", s, e)))
     }
 }
 
@@ -151,10 +223,262 @@ pub trait NumHelper<NumT> {
     fn sum(&self) -> NumT;
 }
 
-impl NumHelper<i64> for Vec<i64> {
-    fn sum(&self) -> i64 {
-        self.iter().copied().reduce(|l, r| l + r).unwrap()
+macro_rules! num_helper {
+    ($($t:ty),*) => {
+        $(impl NumHelper<$t> for Vec<$t> {
+            fn sum(&self) -> $t {
+                self.iter().copied().reduce(|l, r| l + r).unwrap()
+            }
+        })*
+    };
+}
+
+num_helper!(I64, Usize, I128);
+
+/// Integer types used by the solutions. Most code uses I64 (exported as I),
+/// which is also the type of the answers. Days that mostly count or index use
+/// Usize (days 3 and 12), and days whose numbers don't fit into 64 bits use
+/// I128 (day 24, vec2_128 and vec3_128). Modules define their own `type I` as
+/// one of these, and convert between them with the AsInt methods instead of
+/// `as` casts, so that overflows are not silently truncated.
+pub type I64 = i64;
+pub type Usize = usize;
+pub type I128 = i128;
+pub type I = I64;
+
+/// Checked conversions between the integer types above. Panics if the value
+/// does not fit into the target type.
+pub trait AsInt: Copy + std::fmt::Display {
+    fn as_i64(self) -> I64;
+    fn as_usize(self) -> Usize;
+    fn as_i128(self) -> I128;
+
+    /// Like the as_* methods, but returns an error instead of panicking
+    fn try_as<T: TryFrom<Self>>(self) -> Result<T, String> {
+        T::try_from(self).map_err(|_| format!("{} does not fit into {}", self, std::any::type_name::<T>()))
+    }
+}
+
+macro_rules! as_int {
+    ($($t:ty),*) => {
+        $(impl AsInt for $t {
+            fn as_i64(self) -> I64 {
+                self.try_as().unwrap()
+            }
+
+            fn as_usize(self) -> Usize {
+                self.try_as().unwrap()
+            }
+
+            fn as_i128(self) -> I128 {
+                self.try_as().unwrap()
+            }
+        })*
+    };
+}
+
+as_int!(i32, u32, u64, I64, Usize, I128);
+
+/// Returns the min and max corners of the bounding box of the points, in a
+/// single pass. Returns (0, 0) for both corners if there are no points.
+pub fn bounds(points: impl IntoIterator<Item = vec2::Vec2>) -> (vec2::Vec2, vec2::Vec2) {
+    points.into_iter()
+        .fold(None, |bounds: Option<(vec2::Vec2, vec2::Vec2)>, point| match bounds {
+            Some((min, max)) => Some((min.min(point), max.max(point))),
+            None => Some((point, point)),
+        })
+        .unwrap_or_default()
+}
+
+/// Flips rows and columns of a grid. All rows must have the same length.
+pub fn transpose<T: Clone>(grid: &[Vec<T>]) -> Vec<Vec<T>> {
+    let w = grid.first().map_or(0, |row| row.len());
+    assert!(grid.iter().all(|row| row.len() == w), "Can't transpose a grid with rows of different lengths");
+    (0..w).map(|x| {
+        grid.iter().map(|row| row[x].clone()).vec()
+    }).vec()
+}
+
+/// Removes the common leading whitespace from all lines, e.g. for inputs that
+/// are written as indented string literals in tests. The first line is not
+/// used to find the common indentation, since in literals like "\ or r"abc it
+/// starts right after the quote. A leading empty line and a trailing
+/// whitespace-only line are removed.
+///
+/// Most days trim each line (or split by whitespace) while parsing, so they
+/// accept indented input as it is. Day 5 also splits its blocks on
+/// whitespace-only lines and trims each line, so indented input works there,
+/// too. Day 3 keeps whitespace significant (every character is a cell of the
+/// schematic), so its input must be dedented.
+pub fn dedent(input: &str) -> String {
+    let mut lines = input.lines().collect::<Vec<_>>();
+    if lines.first().is_some_and(|line| line.is_empty()) {
+        lines.remove(0);
+    }
+    if lines.len() > 1 && lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
     }
+
+    let indent_of = |line: &&str| line.len() - line.trim_start().len();
+    let indent = lines.iter()
+        .skip(if lines.len() > 1 { 1 } else { 0 })
+        .filter(|line| !line.trim().is_empty())
+        .map(indent_of)
+        .min()
+        .unwrap_or(0);
+
+    lines.iter()
+        .map(|line| &line[indent_of(line).min(indent)..])
+        .join("\n")
 }
 
-pub type I = i64;
+/// Splits the input into lines, like `split("\n")`, but also strips the `\r` of
+/// CRLF line endings (e.g. from inputs saved on Windows)
+pub fn lines(input: &str) -> impl Iterator<Item=&str> {
+    input.split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line))
+}
+
+/// Returns true if the AOC_VERBOSE environment variable is set.
+/// Solutions can use this to print additional debug output.
+pub fn verbose() -> bool {
+    std::env::var_os("AOC_VERBOSE").is_some()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_as_int() {
+        assert_eq!(42usize.as_i64(), 42);
+        assert_eq!((-3i64).as_i128(), -3);
+        assert_eq!(7i128.as_usize(), 7);
+        assert_eq!(u32::MAX.as_i64(), 4294967295);
+        assert_eq!(((1i128 << 63) - 1).as_i64(), i64::MAX);
+
+        assert_eq!((-1i64).try_as::<Usize>(), Err("-1 does not fit into usize".to_string()));
+        assert!((1i128 << 63).try_as::<I64>().is_err());
+        assert!(u64::MAX.try_as::<I64>().is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "does not fit into i64")]
+    fn test_as_i64_overflow() {
+        (i64::MAX as i128 + 1).as_i64();
+    }
+
+    #[test]
+    fn test_windows2() {
+        assert_eq!([1, 2, 4, 7].into_iter().windows2().vec(), vec![(1, 2), (2, 4), (4, 7)]);
+        assert_eq!([1].into_iter().windows2().vec(), vec![]);
+    }
+
+    #[test]
+    fn test_chunks2() {
+        assert_eq!([1, 2, 3, 4].into_iter().chunks2().vec(), vec![[1, 2], [3, 4]]);
+        assert_eq!([1, 2, 3, 4, 5].into_iter().chunks2().vec(), vec![[1, 2], [3, 4]]);
+        assert_eq!([1].into_iter().chunks2().vec(), Vec::<[i32; 2]>::new());
+    }
+
+    #[test]
+    fn test_try_parse_i64() {
+        let parsed = ["1", "x", "3"].into_iter().try_parse_i64().vec();
+        assert_eq!(parsed[0], Ok(1));
+        assert!(parsed[1].as_ref().unwrap_err().contains("\"x\""));
+        assert_eq!(parsed[2], Ok(3));
+
+        assert_eq!(["-4", "05"].into_iter().parse_i64().vec(), vec![-4, 5]);
+    }
+
+    #[test]
+    fn test_try_vec() {
+        let ok: Vec<Result<i64, String>> = vec![Ok(1), Ok(2)];
+        assert_eq!(ok.into_iter().try_vec(), Ok(vec![1, 2]));
+
+        // Stops at the first error, without consuming the rest of the iterator
+        let mut visited = vec![];
+        let result = ["1", "x", "y", "3"].into_iter()
+            .inspect(|s| visited.push(*s))
+            .try_parse_i64()
+            .try_vec();
+        assert!(result.unwrap_err().contains("\"x\""));
+        assert_eq!(visited, vec!["1", "x"]);
+    }
+
+    #[test]
+    fn test_bounds() {
+        use vec2::Vec2;
+        let points = [Vec2(3, -2), Vec2(-5, 4), Vec2(0, 0), Vec2(7, 1), Vec2(2, 9)];
+        assert_eq!(bounds(points), (Vec2(-5, -2), Vec2(7, 9)));
+        assert_eq!(bounds([Vec2(3, 4)]), (Vec2(3, 4), Vec2(3, 4)));
+        assert_eq!(bounds([]), (Vec2(0, 0), Vec2(0, 0)));
+    }
+
+    #[test]
+    fn test_transpose() {
+        let grid = vec![vec![1, 2, 3], vec![4, 5, 6]];
+        assert_eq!(transpose(&grid), vec![vec![1, 4], vec![2, 5], vec![3, 6]]);
+        assert_eq!(transpose(&transpose(&grid)), grid);
+
+        let grid = vec![vec!['a', 'b'], vec!['c', 'd']];
+        assert_eq!(transpose(&grid), vec![vec!['a', 'c'], vec!['b', 'd']]);
+
+        assert!(transpose::<i32>(&[]).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_transpose_ragged() {
+        transpose(&[vec![1, 2], vec![3]]);
+    }
+
+    #[test]
+    fn test_lines() {
+        assert_eq!(lines("a\r\nb\nc\r").vec(), vec!["a", "b", "c"]);
+        assert_eq!(lines("a\n\r\nb").vec(), vec!["a", "", "b"]);
+        assert_eq!(lines("a\r\n").vec(), vec!["a", ""]);
+    }
+
+    #[test]
+    fn test_dedent() {
+        let input = "\
+            467..114..
+              ...*......
+            ..35..633.";
+        assert_eq!(dedent(input), "467..114..\n  ...*......\n..35..633.");
+
+        let input = "
+            a
+            b
+            ";
+        assert_eq!(dedent(input), "a\nb");
+
+        assert_eq!(dedent("    a\n    b"), "a\nb");
+        assert_eq!(dedent("a\n\n  b"), "a\n\nb");
+        assert_eq!(dedent("abc"), "abc");
+        assert_eq!(dedent(""), "");
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(parse::hex("70c71"), Ok(461937));
+        assert_eq!(parse::hex("0"), Ok(0));
+        assert_eq!(parse::hex("FFff"), Ok(65535));
+        assert_eq!(parse::hex(""), Err("Empty hex number".to_string()));
+        assert!(parse::hex("70g71").unwrap_err().contains("'g'"));
+        assert!(parse::hex("+1").is_err());
+        assert!(parse::hex("ffffffffffffffffff").is_err());
+    }
+
+    #[test]
+    fn test_blocks() {
+        let input = "\n  \n  a\n  b\n\n\n \nc \n\n";
+        assert_eq!(parse::blocks(input), vec![vec!["a", "b"], vec!["c"]]);
+        assert_eq!(parse::blocks_untrimmed(input), vec![vec!["  a", "  b"], vec!["c "]]);
+
+        assert_eq!(parse::blocks("a\r\n\r\nb"), vec![vec!["a"], vec!["b"]]);
+        assert!(parse::blocks("").is_empty());
+        assert!(parse::blocks("\n \n").is_empty());
+    }
+}
@@ -1,14 +1,22 @@
+use std::collections::HashMap;
 use std::fs;
-use std::time::Instant;
-use colored::Colorize;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use colored::{ColoredString, Colorize};
+use itertools::Itertools;
 use list_files_macro::list_files;
+use rayon::prelude::*;
 use regex::Regex;
 
-pub type SolutionFn = dyn Fn(&str) -> i64;
-pub type Solution = (u32, Box<SolutionFn>, Box<SolutionFn>);
+pub type SolutionFn = dyn Fn(&str) -> i64 + Send + Sync;
+/// Runs both parts at once, for days that share work between the parts (see
+/// SolutionWithContext)
+pub type BothPartsFn = dyn Fn(&str) -> (PartResult, PartResult) + Send + Sync;
+pub type Solution = (u32, Box<SolutionFn>, Box<SolutionFn>, Option<Box<BothPartsFn>>);
 
 // Loads a list of all solution functions, by searching for "day*.rs" files,
-// loading them as modules, and wrapping the part1 and part2 functions in closures
+// loading them as modules, and wrapping the part1 and part2 functions in closures.
+// Days that implement SolutionWithContext also get a combined entry for both parts.
 pub fn solutions() -> Vec::<Solution> {
     let mut solutions: Vec::<Solution> = vec![];
     let solution_file_regex = Regex::new(r"day(\d+).rs$").unwrap();
@@ -16,12 +24,16 @@ pub fn solutions() -> Vec::<Solution> {
         ($file:expr) => {
             {
                 let day_number = solution_file_regex.captures($file).unwrap().get(1).unwrap().as_str().parse::<u32>().unwrap();
+                // Only part1 and part2 are used from this copy of the module,
+                // the rest of its API is used through crate::dayXX
+                #[allow(dead_code)]
                 #[path = $file]
                 mod day_solution;
                 solutions.push((
                     day_number,
                     Box::new((|input: &str| day_solution::part1(input))),
                     Box::new((|input: &str| day_solution::part2(input))),
+                    both_parts(day_number),
                 ));
             }
         };
@@ -32,21 +44,465 @@ pub fn solutions() -> Vec::<Solution> {
     solutions
 }
 
-pub fn run_solution_part(part: u32, solution: &Box<SolutionFn>, input: &str) {
-    // Runs the solution, measuring the time it takes
+/// Optional pattern for solutions where both parts share an expensive step
+/// (like parsing and preprocessing the input). The context is computed once,
+/// and both parts are computed from it.
+pub trait SolutionWithContext {
+    type Context;
+
+    /// Computes the context shared by both parts
+    fn context(input: &str) -> Self::Context;
+    fn part1(context: &Self::Context) -> i64;
+    fn part2(context: &Self::Context) -> i64;
+}
+
+/// Runs both parts of a SolutionWithContext, computing the context only once
+pub fn run_with_context<S: SolutionWithContext>(input: &str) -> (i64, i64) {
+    let context = S::context(input);
+    (S::part1(&context), S::part2(&context))
+}
+
+/// Same as run_with_context, but measures the time of both parts. The time for
+/// computing the context is counted for part 1.
+pub fn run_with_context_timed<S: SolutionWithContext>(input: &str) -> (PartResult, PartResult) {
+    let now = Instant::now();
+    let context = S::context(input);
+    let answer = S::part1(&context);
+    let part1 = PartResult { answer, micros: now.elapsed().as_micros() };
+
+    let now = Instant::now();
+    let answer = S::part2(&context);
+    let part2 = PartResult { answer, micros: now.elapsed().as_micros() };
+
+    (part1, part2)
+}
+
+/// Combined entry for both parts of the days that implement SolutionWithContext
+fn both_parts(day: u32) -> Option<Box<BothPartsFn>> {
+    match day {
+        22 => Some(Box::new(run_with_context_timed::<crate::day22::Day22>)),
+        25 => Some(Box::new(run_with_context_timed::<crate::day25::Day25>)),
+        _ => None,
+    }
+}
+
+/// Checks that the days of the solutions are 1..=N, without duplicates or gaps
+/// (e.g. from a misnamed day file)
+pub fn validate_solutions(solutions: &[Solution]) -> Result<(), String> {
+    let days = solutions.iter().map(|(day, ..)| *day).sorted().collect::<Vec<_>>();
+    if let Some((day, _)) = days.iter().tuple_windows().find(|(a, b)| a == b) {
+        return Err(format!("Duplicate solution for day {}", day));
+    }
+    if let Some(missing) = (1..=days.len() as u32).find(|day| days.binary_search(day).is_err()) {
+        return Err(format!("Missing solution for day {}", missing));
+    }
+    Ok(())
+}
+
+/// Keys the solutions (see solutions) by day
+pub fn solutions_map(solutions: Vec<Solution>) -> HashMap<u32, Solution> {
+    solutions.into_iter().map(|solution| (solution.0, solution)).collect()
+}
+
+/// How run_solution_day prints the results
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum OutputFormat {
+    /// Colored text for humans
+    Human,
+    /// One JSON object per day, for scripts
+    Json,
+}
+
+/// Answer of a single part, and the time it took to compute it
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct PartResult {
+    pub answer: i64,
+    pub micros: u128,
+}
+
+/// Answers and timings of both parts of a day
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct DayResult {
+    pub day: u32,
+    pub part1: PartResult,
+    pub part2: PartResult,
+}
+
+/// Runs the solution, measuring the time it takes
+pub fn run_solution_part(solution: &SolutionFn, input: &str) -> PartResult {
     let now = Instant::now();
-    let result = solution(&input);
-    let elapsed = now.elapsed();
-    let elapsed_str = format!("({:.2?}ms)", elapsed.as_secs_f32() * 1000.0).dimmed();
-    println!("Part {}: {} {}", part, result.to_string().yellow().bold(), elapsed_str);
+    let answer = solution(input);
+    let micros = now.elapsed().as_micros();
+    PartResult { answer, micros }
+}
+
+/// Runs both parts of the solution, using the combined entry if there is one
+pub fn run_solution_parts(solution: &Solution, input: &str) -> (PartResult, PartResult) {
+    match &solution.3 {
+        Some(both) => both(input),
+        None => (run_solution_part(&*solution.1, input), run_solution_part(&*solution.2, input)),
+    }
+}
+
+/// Reads the optional per-part time budget in milliseconds from the
+/// AOC_BUDGET_MS environment variable
+pub fn budget_from_env() -> Option<Duration> {
+    std::env::var("AOC_BUDGET_MS").ok()
+        .and_then(|ms| ms.trim().parse::<u64>().ok())
+        .map(Duration::from_millis)
+}
+
+/// Formats the elapsed time of a part, in red bold if it exceeds the budget
+pub fn format_elapsed(micros: u128, budget: Option<Duration>) -> ColoredString {
+    let elapsed_str = format!("({:.2?}ms)", micros as f32 / 1000.0);
+    match budget {
+        Some(budget) if micros > budget.as_micros() => elapsed_str.red().bold(),
+        _ => elapsed_str.dimmed(),
+    }
+}
+
+pub fn print_part_result(part: u32, result: &PartResult, budget: Option<Duration>) {
+    let elapsed_str = format_elapsed(result.micros, budget);
+    println!("Part {}: {} {}", part, result.answer.to_string().yellow().bold(), elapsed_str);
+}
+
+/// Formats the results of a day as a JSON object, e.g.
+/// `{"day":5,"part1":{"answer":35,"micros":1234},"part2":{"answer":46,"micros":5678}}`
+pub fn format_json(day: u32, part1: &PartResult, part2: &PartResult) -> String {
+    let part_json = |result: &PartResult| format!("{{\"answer\":{},\"micros\":{}}}", result.answer, result.micros);
+    format!("{{\"day\":{},\"part1\":{},\"part2\":{}}}", day, part_json(part1), part_json(part2))
+}
+
+/// Parses the input of the given day and formats the parsed structure as JSON,
+/// for debugging. Returns None for days that don't support this.
+#[cfg(feature = "dump-parsed")]
+pub fn dump_parsed(day: u32, input: &str) -> Option<String> {
+    match day {
+        19 => Some(crate::day19::dump_parsed(input)),
+        22 => Some(crate::day22::dump_parsed(input)),
+        24 => Some(crate::day24::dump_parsed(input)),
+        _ => None,
+    }
+}
+
+/// Path of the puzzle input file for the given day
+pub fn input_path(day: u32) -> String {
+    format!("inputs/day{:02}.txt", day)
+}
+
+/// What is available for a day, shown by `--list`
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct DayStatus {
+    pub day: u32,
+    pub has_solution: bool,
+    pub has_input: bool,
+    pub has_example: bool,
+}
+
+/// Checks for each day 1..=25 if there is a solution, an input file, and an
+/// example input (see examples::EXAMPLES)
+pub fn day_statuses(solutions: &[Solution]) -> Vec<DayStatus> {
+    (1..=25).map(|day| DayStatus {
+        day,
+        has_solution: solutions.iter().any(|(solution_day, ..)| *solution_day == day),
+        has_input: Path::new(&input_path(day)).exists(),
+        has_example: crate::examples::has_example(day),
+    }).collect()
+}
+
+/// Formats the day statuses as a table with one line per day
+pub fn format_day_list(statuses: &[DayStatus]) -> String {
+    let yes_no = |value: bool| if value { "yes" } else { "no" };
+    statuses.iter()
+        .map(|status| format!(
+            "Day {:02}  solution: {:<3}  input: {:<3}  example: {}",
+            status.day, yes_no(status.has_solution), yes_no(status.has_input), yes_no(status.has_example)
+        ))
+        .join("\n")
+}
+
+/// URL of the puzzle input for the given day
+pub fn input_url(day: u32) -> String {
+    format!("https://adventofcode.com/2023/day/{}/input", day)
+}
+
+/// User agent for downloading inputs. Advent of Code asks automated tools to
+/// identify themselves and to include contact information.
+pub const USER_AGENT: &str = "github.com/LukasBoersma/Advent-of-Code-2023 by mail@lukas-boersma.com";
+
+/// Config for curl (see `curl --config`) to download the input of the given
+/// day. The session is passed in the config instead of the command line, so
+/// that other users can't see it in the process list.
+#[cfg(any(test, feature = "download"))]
+pub fn curl_config(day: u32, session: &str) -> String {
+    let quote = |value: &str| format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""));
+    [
+        format!("url = {}", quote(&input_url(day))),
+        format!("cookie = {}", quote(&format!("session={}", session))),
+        format!("user-agent = {}", quote(USER_AGENT)),
+    ].join("\n") + "\n"
+}
+
+/// Downloads the puzzle input for the given day, using the session cookie from
+/// adventofcode.com, and caches it in the inputs folder.
+/// Uses curl, so that we don't need an HTTP library.
+#[cfg(feature = "download")]
+pub fn fetch_input(day: u32, session: &str) -> Result<String, String> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    // Read the config with the session from stdin
+    let mut curl = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Unable to run curl: {}", e))?;
+    curl.stdin.take().unwrap()
+        .write_all(curl_config(day, session).as_bytes())
+        .map_err(|e| format!("Unable to pass the config to curl: {}", e))?;
+    let output = curl.wait_with_output().map_err(|e| format!("Unable to run curl: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("Unable to download input for day {}: {}", day, String::from_utf8_lossy(&output.stderr)));
+    }
+
+    // Trailing line endings are not supported by the solutions, so remove them
+    let input = String::from_utf8(output.stdout).map_err(|e| e.to_string())?.trim_end().to_string();
+
+    fs::create_dir_all("inputs").map_err(|e| e.to_string())?;
+    fs::write(input_path(day), &input).map_err(|e| e.to_string())?;
+
+    Ok(input)
+}
+
+/// Loads the puzzle input for the given day. If the input file is missing,
+/// the download feature is enabled, and the AOC_SESSION environment variable is
+/// set, the input is downloaded.
+pub fn load_input(day: u32) -> String {
+    if let Ok(input) = fs::read_to_string(input_path(day)) {
+        return input;
+    }
+
+    #[cfg(feature = "download")]
+    if let Ok(session) = std::env::var("AOC_SESSION") {
+        return fetch_input(day, &session).expect("Unable to download puzzle input");
+    }
+
+    panic!("Unable to read puzzle input file {}", input_path(day))
+}
+
+/// Path of the optional file with the known answers, mapping the day to the
+/// answers of both parts, e.g. `{ "1": [142, 281], "2": [8, 2286] }`
+pub const ANSWERS_PATH: &str = "inputs/answers.json";
+
+/// Parses the answers file contents. Only supports the simple format described
+/// at ANSWERS_PATH, so that we don't need a JSON library.
+pub fn parse_answers(json: &str) -> Result<HashMap<u32, (i64, i64)>, String> {
+    let json: String = json.chars().filter(|c| !c.is_whitespace()).collect();
+    let entry = r#""(\d+)":\[(-?\d+),(-?\d+)\]"#;
+    let file_regex = Regex::new(&format!(r"^\{{({0}(,{0})*)?\}}$", entry)).unwrap();
+    if !file_regex.is_match(&json) {
+        return Err(format!("Invalid answers file: {}", json));
+    }
+
+    Regex::new(entry).unwrap().captures_iter(&json).map(|c| {
+        let day = c[1].parse::<u32>().map_err(|e| e.to_string())?;
+        let part1 = c[2].parse::<i64>().map_err(|e| e.to_string())?;
+        let part2 = c[3].parse::<i64>().map_err(|e| e.to_string())?;
+        Ok((day, (part1, part2)))
+    }).collect()
+}
+
+/// Loads the known answers from ANSWERS_PATH, see parse_answers
+pub fn load_answers() -> Result<HashMap<u32, (i64, i64)>, String> {
+    let json = fs::read_to_string(ANSWERS_PATH).map_err(|e| format!("Unable to read {}: {}", ANSWERS_PATH, e))?;
+    parse_answers(&json)
 }
 
-pub fn run_solution_day(solution: Solution) {
+/// Runs both parts of the solution and prints the results. Parts that take
+/// longer than the optional budget are highlighted.
+pub fn run_solution_day(solution: Solution, format: OutputFormat, budget: Option<Duration>) {
     // Load the puzzle input
-    let input = fs::read_to_string(format!("inputs/day{:02}.txt", solution.0)).expect("Unable to read puzzle input file");
+    let input = load_input(solution.0);
 
     // Run the solution for both parts
-    println!("Day {}", solution.0);
-    run_solution_part(1, &solution.1, &input);
-    run_solution_part(2, &solution.2, &input);
-}
\ No newline at end of file
+    let (part1, part2) = run_solution_parts(&solution, &input);
+    let result = DayResult { day: solution.0, part1, part2 };
+    print_day_result(&result, format, budget);
+}
+
+pub fn print_day_result(result: &DayResult, format: OutputFormat, budget: Option<Duration>) {
+    match format {
+        OutputFormat::Human => {
+            println!("Day {}", result.day);
+            print_part_result(1, &result.part1, budget);
+            print_part_result(2, &result.part2, budget);
+        },
+        OutputFormat::Json => println!("{}", format_json(result.day, &result.part1, &result.part2)),
+    }
+}
+
+/// Runs all solutions in parallel, using the given function to load the input
+/// of each day. Days without input are skipped. The results are sorted by day.
+pub fn run_all_with(solutions: &[Solution], load: impl Fn(u32) -> Option<String> + Sync) -> Vec<DayResult> {
+    let mut results = solutions.par_iter()
+        .filter_map(|solution| {
+            let input = load(solution.0)?;
+            let (part1, part2) = run_solution_parts(solution, &input);
+            Some(DayResult { day: solution.0, part1, part2 })
+        })
+        .collect::<Vec<_>>();
+
+    results.sort_by_key(|result| result.day);
+    results
+}
+
+/// Runs all solutions that have an input file in parallel, and returns the
+/// results sorted by day
+pub fn run_all(solutions: &[Solution]) -> Vec<DayResult> {
+    run_all_with(solutions, |day| fs::read_to_string(input_path(day)).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_location() {
+        assert_eq!(input_url(5), "https://adventofcode.com/2023/day/5/input");
+        assert_eq!(input_url(25), "https://adventofcode.com/2023/day/25/input");
+        assert_eq!(input_path(5), "inputs/day05.txt");
+        assert_eq!(input_path(25), "inputs/day25.txt");
+    }
+
+    #[test]
+    fn test_day_statuses() {
+        let statuses = day_statuses(&solutions());
+        assert_eq!(statuses.len(), 25);
+        assert!(statuses.iter().all(|status| status.has_solution));
+
+        // Day 21's examples use other step counts than the puzzle, so they are not in EXAMPLES
+        let with_example = statuses.iter().filter(|status| status.has_example).map(|status| status.day).collect::<Vec<_>>();
+        assert_eq!(with_example, (1..=25).filter(|&day| day != 21).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_curl_config() {
+        let config = curl_config(5, "abc123");
+        assert_eq!(config, format!("\
+            url = \"https://adventofcode.com/2023/day/5/input\"
+            cookie = \"session=abc123\"
+            user-agent = \"{}\"
+            ", USER_AGENT).replace("            ", ""));
+
+        // Quotes and backslashes are escaped, so they can't end the value early
+        assert!(curl_config(5, "a\"b\\c").contains(r#"cookie = "session=a\"b\\c""#));
+    }
+
+    #[test]
+    fn test_format_day_list() {
+        let statuses = [
+            DayStatus { day: 1, has_solution: true, has_input: true, has_example: true },
+            DayStatus { day: 2, has_solution: true, has_input: false, has_example: true },
+            DayStatus { day: 25, has_solution: false, has_input: false, has_example: false },
+        ];
+        assert_eq!(format_day_list(&statuses), "\
+            Day 01  solution: yes  input: yes  example: yes
+            Day 02  solution: yes  input: no   example: yes
+            Day 25  solution: no   input: no   example: no".replace("            ", ""));
+        assert_eq!(format_day_list(&[]), "");
+    }
+
+    #[test]
+    fn test_solutions_map() {
+        let solutions = solutions_map(solutions());
+        assert_eq!(solutions.len(), 25);
+        assert!((1..=25).all(|day| solutions.contains_key(&day)));
+        assert!(solutions.iter().all(|(&day, solution)| solution.0 == day));
+    }
+
+    #[test]
+    fn test_validate_solutions() {
+        let part = || -> Box<SolutionFn> { Box::new(|_: &str| 0) };
+        let fake = |days: &[u32]| -> Vec<Solution> {
+            days.iter().map(|&day| (day, part(), part(), None)).collect()
+        };
+
+        assert_eq!(validate_solutions(&solutions()), Ok(()));
+        assert_eq!(validate_solutions(&fake(&[2, 1, 3])), Ok(()));
+        assert_eq!(validate_solutions(&fake(&[])), Ok(()));
+        assert_eq!(validate_solutions(&fake(&[1, 2, 4])), Err("Missing solution for day 3".to_string()));
+        assert_eq!(validate_solutions(&fake(&[1, 2, 2, 3])), Err("Duplicate solution for day 2".to_string()));
+    }
+
+    #[test]
+    fn test_format_json() {
+        let part1 = PartResult { answer: 35, micros: 1234 };
+        let part2 = PartResult { answer: -46, micros: 0 };
+        assert_eq!(
+            format_json(5, &part1, &part2),
+            r#"{"day":5,"part1":{"answer":35,"micros":1234},"part2":{"answer":-46,"micros":0}}"#
+        );
+    }
+
+    #[test]
+    fn test_format_elapsed() {
+        let budget = Some(Duration::from_millis(200));
+        assert_eq!(format_elapsed(1234, None), "(1.23ms)".dimmed());
+        assert_eq!(format_elapsed(1234, budget), "(1.23ms)".dimmed());
+        assert_eq!(format_elapsed(200_000, budget), "(200.00ms)".dimmed());
+        assert_eq!(format_elapsed(250_000, budget), "(250.00ms)".red().bold());
+    }
+
+    #[test]
+    fn test_run_all_sorted_by_day() {
+        // Earlier days take longer, so they finish last
+        let solution = |day: u32| -> Solution {
+            let slow = move |input: &str| {
+                std::thread::sleep(Duration::from_millis(10 * (5 - day as u64)));
+                input.len() as i64
+            };
+            (day, Box::new(slow), Box::new(move |_: &str| day as i64), None)
+        };
+        let solutions = vec![solution(3), solution(1), solution(4), solution(2)];
+
+        // Day 3 has no input and is skipped
+        let results = run_all_with(&solutions, |day| (day != 3).then(|| "x".repeat(day as usize)));
+        assert_eq!(results.iter().map(|r| r.day).collect::<Vec<_>>(), vec![1, 2, 4]);
+        assert_eq!(results.iter().map(|r| (r.part1.answer, r.part2.answer)).collect::<Vec<_>>(), vec![(1, 1), (2, 2), (4, 4)]);
+    }
+
+    #[test]
+    fn test_run_both_parts() {
+        let part = || -> Box<SolutionFn> { Box::new(|_: &str| panic!("Parts must not run separately")) };
+        let both = |input: &str| {
+            let answer = input.len() as i64;
+            (PartResult { answer, micros: 0 }, PartResult { answer: -answer, micros: 0 })
+        };
+        let fake: Vec<Solution> = vec![(1, part(), part(), Some(Box::new(both)))];
+
+        let results = run_all_with(&fake, |_| Some("abc".to_string()));
+        assert_eq!((results[0].part1.answer, results[0].part2.answer), (3, -3));
+
+        // Day 22 shares the settled stack, and day 25 the cut between the parts
+        let solutions = solutions_map(solutions());
+        assert!(solutions[&22].3.is_some());
+        assert!(solutions[&25].3.is_some());
+        assert!(solutions[&1].3.is_none());
+    }
+
+    #[test]
+    fn test_parse_answers() {
+        let answers = parse_answers("{\n  \"1\": [142, 281],\n  \"24\": [2, -47]\n}").unwrap();
+        assert_eq!(answers.len(), 2);
+        assert_eq!(answers[&1], (142, 281));
+        assert_eq!(answers[&24], (2, -47));
+
+        assert_eq!(parse_answers("{}").unwrap().len(), 0);
+        assert!(parse_answers("{\"1\": [142]}").is_err());
+        assert!(parse_answers("{\"1\": [142, 281],}").is_err());
+        assert!(parse_answers("[142, 281]").is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use std::fmt;
 use std::ops::{Add, AddAssign, Sub, SubAssign, Mul, Div};
 
 type I = i64;
@@ -68,6 +69,30 @@ impl Vec2 {
         self.0.checked_add(other.0).and_then(|x| self.1.checked_add(other.1).map(|y| Vec2(x, y)))
     }
 
+    /// Component-wise sign (-1, 0 or 1)
+    pub fn signum(&self) -> Self {
+        Vec2(self.0.signum(), self.1.signum())
+    }
+
+    /// Returns the next cell when moving one step from self toward the target.
+    /// Moves diagonally if the target is not on the same row or column.
+    pub fn step_toward(&self, target: Self) -> Self {
+        *self + (target - *self).signum()
+    }
+
+    /// Index of the position in a flat, row-major grid with the given width
+    pub fn to_index(&self, width: I) -> usize {
+        assert!(width > 0, "Grid width must be positive");
+        assert!(self.0 >= 0 && self.0 < width && self.1 >= 0, "Position {} is outside of a grid with width {}", self, width);
+        (self.1 * width + self.0) as usize
+    }
+
+    /// Position of the index in a flat, row-major grid with the given width
+    pub fn from_index(index: usize, width: I) -> Self {
+        assert!(width > 0, "Grid width must be positive");
+        Vec2(index as I % width, index as I / width)
+    }
+
 }
 
 impl Add for Vec2
@@ -159,4 +184,65 @@ impl From<(i32,i32)> for Vec2
     fn from(value: (i32, i32)) -> Self {
         Vec2(value.0 as I, value.1 as I)
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for Vec2 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.0, self.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index() {
+        let width = 7;
+        for pos in [Vec2(0, 0), Vec2(6, 0), Vec2(0, 1), Vec2(3, 4), Vec2(6, 9)] {
+            assert_eq!(Vec2::from_index(pos.to_index(width), width), pos);
+        }
+        assert_eq!(Vec2(3, 4).to_index(width), 31);
+        assert_eq!(Vec2::from_index(31, width), Vec2(3, 4));
+        assert_eq!(Vec2::from_index(7, width), Vec2(0, 1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_out_of_bounds() {
+        Vec2(7, 0).to_index(7);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Vec2(1, 2).to_string(), "(1, 2)");
+        assert_eq!(Vec2(-4, 0).to_string(), "(-4, 0)");
+        assert_eq!(Vec2(0, -123).to_string(), "(0, -123)");
+    }
+
+    #[test]
+    fn test_signum() {
+        assert_eq!(Vec2(5, -3).signum(), Vec2(1, -1));
+        assert_eq!(Vec2(0, 7).signum(), Vec2(0, 1));
+        assert_eq!(Vec2(0, 0).signum(), Vec2(0, 0));
+    }
+
+    #[test]
+    fn test_step_toward() {
+        // Axis aligned
+        assert_eq!(Vec2(2, 3).step_toward(Vec2(2, 10)), Vec2(2, 4));
+        assert_eq!(Vec2(2, 3).step_toward(Vec2(-5, 3)), Vec2(1, 3));
+
+        // Diagonal
+        assert_eq!(Vec2(0, 0).step_toward(Vec2(3, -3)), Vec2(1, -1));
+        assert_eq!(Vec2(0, 0).step_toward(Vec2(-1, 5)), Vec2(-1, 1));
+
+        // Already there
+        assert_eq!(Vec2(4, 4).step_toward(Vec2(4, 4)), Vec2(4, 4));
+
+        // Walking along a line visits every cell
+        let (start, end) = (Vec2(1, 1), Vec2(1, 4));
+        let cells = std::iter::successors(Some(start), |&p| (p != end).then(|| p.step_toward(end))).collect::<Vec<_>>();
+        assert_eq!(cells, vec![Vec2(1, 1), Vec2(1, 2), Vec2(1, 3), Vec2(1, 4)]);
+    }
+}
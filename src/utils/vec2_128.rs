@@ -1,5 +1,8 @@
+use std::fmt;
 use std::ops::{Add, AddAssign, Sub, SubAssign, Mul, Div};
 
+use crate::vec2::Vec2;
+
 type I = i128;
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
@@ -68,6 +71,17 @@ impl Vec2L {
         self.0.checked_add(other.0).and_then(|x| self.1.checked_add(other.1).map(|y| Vec2L(x, y)))
     }
 
+    /// Component-wise sign (-1, 0 or 1)
+    pub fn signum(&self) -> Self {
+        Vec2L(self.0.signum(), self.1.signum())
+    }
+
+    /// Returns the next cell when moving one step from self toward the target.
+    /// Moves diagonally if the target is not on the same row or column.
+    pub fn step_toward(&self, target: Self) -> Self {
+        *self + (target - *self).signum()
+    }
+
 }
 
 impl Add for Vec2L
@@ -159,4 +173,76 @@ impl From<(i32,i32)> for Vec2L
     fn from(value: (i32, i32)) -> Self {
         Vec2L(value.0 as I, value.1 as I)
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for Vec2L {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.0, self.1)
+    }
+}
+
+impl From<Vec2> for Vec2L {
+    fn from(value: Vec2) -> Self {
+        Vec2L(value.0 as I, value.1 as I)
+    }
+}
+
+impl TryFrom<Vec2L> for Vec2 {
+    type Error = String;
+
+    fn try_from(value: Vec2L) -> Result<Self, Self::Error> {
+        match (i64::try_from(value.0), i64::try_from(value.1)) {
+            (Ok(x), Ok(y)) => Ok(Vec2(x, y)),
+            _ => Err(format!("{} does not fit into Vec2", value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Vec2L(1, 2).to_string(), "(1, 2)");
+        assert_eq!(Vec2L(-4, 0).to_string(), "(-4, 0)");
+        assert_eq!(Vec2L(0, -123).to_string(), "(0, -123)");
+    }
+
+    #[test]
+    fn test_signum() {
+        assert_eq!(Vec2L(5, -3).signum(), Vec2L(1, -1));
+        assert_eq!(Vec2L(0, 7).signum(), Vec2L(0, 1));
+        assert_eq!(Vec2L(0, 0).signum(), Vec2L(0, 0));
+    }
+
+    #[test]
+    fn test_step_toward() {
+        // Axis aligned
+        assert_eq!(Vec2L(2, 3).step_toward(Vec2L(2, 10)), Vec2L(2, 4));
+        assert_eq!(Vec2L(2, 3).step_toward(Vec2L(-5, 3)), Vec2L(1, 3));
+
+        // Diagonal
+        assert_eq!(Vec2L(0, 0).step_toward(Vec2L(3, -3)), Vec2L(1, -1));
+        assert_eq!(Vec2L(0, 0).step_toward(Vec2L(-1, 5)), Vec2L(-1, 1));
+
+        // Already there
+        assert_eq!(Vec2L(4, 4).step_toward(Vec2L(4, 4)), Vec2L(4, 4));
+
+        // Walking along a line visits every cell
+        let (start, end) = (Vec2L(1, 1), Vec2L(1, 4));
+        let cells = std::iter::successors(Some(start), |&p| (p != end).then(|| p.step_toward(end))).collect::<Vec<_>>();
+        assert_eq!(cells, vec![Vec2L(1, 1), Vec2L(1, 2), Vec2L(1, 3), Vec2L(1, 4)]);
+    }
+
+    #[test]
+    fn test_precision_conversion() {
+        let v = Vec2(i64::MIN, i64::MAX);
+        let wide = Vec2L::from(v);
+        assert_eq!(wide, Vec2L(i64::MIN as I, i64::MAX as I));
+        assert_eq!(Vec2::try_from(wide), Ok(v));
+
+        assert!(Vec2::try_from(Vec2L(i64::MAX as I + 1, 0)).is_err());
+        assert!(Vec2::try_from(Vec2L(0, i64::MIN as I - 1)).is_err());
+    }
+}
@@ -1,9 +1,22 @@
 use crate::vec2::Vec2;
+use std::fmt;
 use std::ops::{Add, AddAssign, Sub, SubAssign, Mul, Div};
 
 
 type I = i64;
 
+/// One of the three coordinate axes, for accessing vector components generically
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    pub const ALL: [Axis; 3] = [Axis::X, Axis::Y, Axis::Z];
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
 pub struct Vec3 {
     pub x: I,
@@ -70,6 +83,14 @@ impl Vec3 {
         Vec2(self.x, self.y)
     }
 
+    pub fn xz(&self) -> Vec2 {
+        Vec2(self.x, self.z)
+    }
+
+    pub fn yz(&self) -> Vec2 {
+        Vec2(self.y, self.z)
+    }
+
     pub fn length_squared(&self) -> I {
         self.x * self.x + self.y * self.y + self.z * self.z
     }
@@ -78,6 +99,30 @@ impl Vec3 {
         self.x as i128 * self.x as i128 + self.y as i128 * self.y as i128 + self.z as i128 * self.z as i128
     }
 
+    pub fn add_checked(&self, other: Self) -> Option<Self> {
+        Some(Vec3 { x: self.x.checked_add(other.x)?, y: self.y.checked_add(other.y)?, z: self.z.checked_add(other.z)? })
+    }
+
+    pub fn sub_checked(&self, other: Self) -> Option<Self> {
+        Some(Vec3 { x: self.x.checked_sub(other.x)?, y: self.y.checked_sub(other.y)?, z: self.z.checked_sub(other.z)? })
+    }
+
+    pub fn get(&self, axis: Axis) -> I {
+        match axis {
+            Axis::X => self.x,
+            Axis::Y => self.y,
+            Axis::Z => self.z,
+        }
+    }
+
+    pub fn set(&mut self, axis: Axis, value: I) {
+        match axis {
+            Axis::X => self.x = value,
+            Axis::Y => self.y = value,
+            Axis::Z => self.z = value,
+        }
+    }
+
     pub fn try_div(self, rhs: Vec3) -> Result<Vec3, ()> {
         if rhs.x != 0 && rhs.y != 0 && rhs.z != 0  {
             Ok(Vec3 { x: self.x / rhs.x, y: self.y / rhs.y, z: self.z / rhs.z })
@@ -191,4 +236,54 @@ impl Div<Vec3> for Vec3 {
     fn div(self, rhs: Vec3) -> Self::Output {
         Vec3 { x: self.x / rhs.x, y: self.y / rhs.y, z: self.z / rhs.z }
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for Vec3 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {}, {})", self.x, self.y, self.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_axis_get_set() {
+        let mut v = Vec3::new(1, -2, 3);
+        assert_eq!(v.get(Axis::X), 1);
+        assert_eq!(v.get(Axis::Y), -2);
+        assert_eq!(v.get(Axis::Z), 3);
+
+        for (i, axis) in Axis::ALL.into_iter().enumerate() {
+            v.set(axis, 10 * (i as I + 1));
+        }
+        assert_eq!(v, Vec3::new(10, 20, 30));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Vec3::new(1, 2, 3).to_string(), "(1, 2, 3)");
+        assert_eq!(Vec3::new(-4, 0, -123).to_string(), "(-4, 0, -123)");
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let a = Vec3::new(i64::MAX - 1, 0, i64::MIN + 1);
+        assert_eq!(a.add_checked(Vec3::new(1, 5, -1)), Some(Vec3::new(i64::MAX, 5, i64::MIN)));
+        assert_eq!(a.add_checked(Vec3::new(2, 0, 0)), None);
+        assert_eq!(a.add_checked(Vec3::new(0, 0, -2)), None);
+
+        assert_eq!(a.sub_checked(Vec3::new(-1, 5, 1)), Some(Vec3::new(i64::MAX, -5, i64::MIN)));
+        assert_eq!(a.sub_checked(Vec3::new(0, 0, 2)), None);
+        assert_eq!(Vec3::zero().sub_checked(Vec3::new(0, i64::MIN, 0)), None);
+    }
+
+    #[test]
+    fn test_swizzles() {
+        let v = Vec3::new(1, -2, 3);
+        assert_eq!(v.xy(), Vec2(1, -2));
+        assert_eq!(v.xz(), Vec2(1, 3));
+        assert_eq!(v.yz(), Vec2(-2, 3));
+    }
+}
@@ -1,6 +1,8 @@
+use std::fmt;
 use std::ops::{Add, AddAssign, Sub, SubAssign, Mul, Div};
 
 use crate::vec2_128::Vec2L;
+use crate::vec3::{Axis, Vec3};
 
 type I = i128;
 
@@ -70,6 +72,14 @@ impl Vec3L {
         Vec2L(self.x, self.y)
     }
 
+    pub fn xz(&self) -> Vec2L {
+        Vec2L(self.x, self.z)
+    }
+
+    pub fn yz(&self) -> Vec2L {
+        Vec2L(self.y, self.z)
+    }
+
     pub fn length_squared(&self) -> I {
         self.x * self.x + self.y * self.y + self.z * self.z
     }
@@ -78,6 +88,30 @@ impl Vec3L {
         self.x as i128 * self.x as i128 + self.y as i128 * self.y as i128 + self.z as i128 * self.z as i128
     }
 
+    pub fn add_checked(&self, other: Self) -> Option<Self> {
+        Some(Vec3L { x: self.x.checked_add(other.x)?, y: self.y.checked_add(other.y)?, z: self.z.checked_add(other.z)? })
+    }
+
+    pub fn sub_checked(&self, other: Self) -> Option<Self> {
+        Some(Vec3L { x: self.x.checked_sub(other.x)?, y: self.y.checked_sub(other.y)?, z: self.z.checked_sub(other.z)? })
+    }
+
+    pub fn get(&self, axis: Axis) -> I {
+        match axis {
+            Axis::X => self.x,
+            Axis::Y => self.y,
+            Axis::Z => self.z,
+        }
+    }
+
+    pub fn set(&mut self, axis: Axis, value: I) {
+        match axis {
+            Axis::X => self.x = value,
+            Axis::Y => self.y = value,
+            Axis::Z => self.z = value,
+        }
+    }
+
     pub fn try_div(self, rhs: Vec3L) -> Result<Vec3L, ()> {
         if rhs.x != 0 && rhs.y != 0 && rhs.z != 0  {
             Ok(Vec3L { x: self.x / rhs.x, y: self.y / rhs.y, z: self.z / rhs.z })
@@ -191,4 +225,83 @@ impl Div<Vec3L> for Vec3L {
     fn div(self, rhs: Vec3L) -> Self::Output {
         Vec3L { x: self.x / rhs.x, y: self.y / rhs.y, z: self.z / rhs.z }
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for Vec3L {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {}, {})", self.x, self.y, self.z)
+    }
+}
+
+impl From<Vec3> for Vec3L {
+    fn from(value: Vec3) -> Self {
+        Vec3L { x: value.x as I, y: value.y as I, z: value.z as I }
+    }
+}
+
+impl TryFrom<Vec3L> for Vec3 {
+    type Error = String;
+
+    fn try_from(value: Vec3L) -> Result<Self, Self::Error> {
+        match (i64::try_from(value.x), i64::try_from(value.y), i64::try_from(value.z)) {
+            (Ok(x), Ok(y), Ok(z)) => Ok(Vec3 { x, y, z }),
+            _ => Err(format!("{} does not fit into Vec3", value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_axis_get_set() {
+        let mut v = Vec3L::new(1, -2, 3);
+        assert_eq!(v.get(Axis::X), 1);
+        assert_eq!(v.get(Axis::Y), -2);
+        assert_eq!(v.get(Axis::Z), 3);
+
+        for (i, axis) in Axis::ALL.into_iter().enumerate() {
+            v.set(axis, 10 * (i as I + 1));
+        }
+        assert_eq!(v, Vec3L::new(10, 20, 30));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Vec3L::new(1, 2, 3).to_string(), "(1, 2, 3)");
+        assert_eq!(Vec3L::new(-4, 0, -123).to_string(), "(-4, 0, -123)");
+    }
+
+    #[test]
+    fn test_precision_conversion() {
+        let v = Vec3::new(i64::MIN, -1, i64::MAX);
+        let wide = Vec3L::from(v);
+        assert_eq!(wide, Vec3L::new(i64::MIN as I, -1, i64::MAX as I));
+        assert_eq!(Vec3::try_from(wide), Ok(v));
+
+        let too_large = Vec3L::new(0, 0, i64::MAX as I + 1);
+        assert!(Vec3::try_from(too_large).unwrap_err().contains("(0, 0, 9223372036854775808)"));
+        assert!(Vec3::try_from(Vec3L::new(i64::MIN as I - 1, 0, 0)).is_err());
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let a = Vec3L::new(i128::MAX - 1, 0, i128::MIN + 1);
+        assert_eq!(a.add_checked(Vec3L::new(1, 5, -1)), Some(Vec3L::new(i128::MAX, 5, i128::MIN)));
+        assert_eq!(a.add_checked(Vec3L::new(2, 0, 0)), None);
+        assert_eq!(a.add_checked(Vec3L::new(0, 0, -2)), None);
+
+        assert_eq!(a.sub_checked(Vec3L::new(-1, 5, 1)), Some(Vec3L::new(i128::MAX, -5, i128::MIN)));
+        assert_eq!(a.sub_checked(Vec3L::new(0, 0, 2)), None);
+        assert_eq!(Vec3L::zero().sub_checked(Vec3L::new(0, i128::MIN, 0)), None);
+    }
+
+    #[test]
+    fn test_swizzles() {
+        let v = Vec3L::new(1, -2, 3);
+        assert_eq!(v.xy(), Vec2L(1, -2));
+        assert_eq!(v.xz(), Vec2L(1, 3));
+        assert_eq!(v.yz(), Vec2L(-2, 3));
+    }
+}
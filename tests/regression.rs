@@ -0,0 +1,31 @@
+// Regression tests that run the solutions on the actual puzzle inputs and
+// compare the results with the known answers in inputs/answers.json.
+// The inputs are not included in this repository, so days without an input
+// file (or without a known answer) are skipped.
+
+use std::fs;
+use std::path::Path;
+
+use advent_of_code_2023::solution_import::*;
+
+#[test]
+fn test_known_answers() {
+    if !Path::new(ANSWERS_PATH).exists() {
+        println!("No {} found, skipping", ANSWERS_PATH);
+        return;
+    }
+    let answers = load_answers().unwrap();
+
+    for (day, part1, part2, _) in solutions() {
+        let Some(&(answer1, answer2)) = answers.get(&day) else {
+            continue;
+        };
+        let Ok(input) = fs::read_to_string(input_path(day)) else {
+            println!("No input for day {}, skipping", day);
+            continue;
+        };
+
+        assert_eq!(part1(&input), answer1, "Wrong answer for day {} part 1", day);
+        assert_eq!(part2(&input), answer2, "Wrong answer for day {} part 2", day);
+    }
+}